@@ -0,0 +1,43 @@
+//! Compares serializing a large map of floats through serde against the fast path for primitive maps.
+//!
+//! Run with `cargo bench -p serde_result_line`.
+
+use std::collections::BTreeMap;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const ENTRIES: usize = 1_000;
+const ITERATIONS: u32 = 1_000;
+
+fn measure(name: &str, mut f: impl FnMut() -> String) -> Duration {
+    // Warm up caches and the allocator before measuring
+    for _ in 0..ITERATIONS / 10 {
+        black_box(f());
+    }
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(f());
+    }
+    let per_iteration = start.elapsed() / ITERATIONS;
+    println!("{name:<16} {per_iteration:>12?} per line");
+    per_iteration
+}
+
+fn main() {
+    let map: BTreeMap<String, f64> = (0..ENTRIES)
+        .map(|i| (format!("measurement_{i}"), i as f64 * 1.5))
+        .collect();
+
+    println!("serializing a map with {ENTRIES} entries, {ITERATIONS} iterations");
+    let serde = measure("to_string", || {
+        serde_result_line::to_string(black_box(&map)).unwrap()
+    });
+    let fast = measure("to_string_map", || {
+        serde_result_line::to_string_map(black_box(&map)).unwrap()
+    });
+    println!(
+        "to_string_map is {:.2}x as fast as to_string",
+        serde.as_secs_f64() / fast.as_secs_f64()
+    );
+}
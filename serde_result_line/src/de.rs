@@ -1,18 +1,122 @@
 use std::str::FromStr;
 
-use nom::combinator::peek;
+use nom::combinator::{eof, peek};
 use nom::sequence::terminated;
 use nom::{
-    bytes::complete::take_until1,
+    bytes::complete::{take_till1, take_until, take_until1},
     character::complete::space1,
     sequence::{delimited, preceded, separated_pair},
     Finish, IResult, Parser,
 };
 
-use crate::ResultItem;
+use crate::{ResultItem, SCHEMA_VERSION_KEY};
+
+/// Options controlling how result lines are parsed.
+/// See [`from_string`] for how the kind of an unquoted value is determined.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+/// use serde_result_line::{ParseOptions, ResultItem};
+///
+/// let options = ParseOptions::new().trim_quoted_values(true);
+/// let map: HashMap<&str, ResultItem> =
+///     serde_result_line::from_string_with_options(r#"RESULT a=" padded ""#, &options).unwrap();
+/// assert_eq!(map["a"], ResultItem::from("padded"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseOptions {
+    trim_quoted_values: bool,
+    decimal_separator: char,
+    key_value_separator: String,
+    sequence_separator: Option<char>,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            trim_quoted_values: false,
+            decimal_separator: '.',
+            key_value_separator: "=".to_owned(),
+            sequence_separator: None,
+        }
+    }
+}
+
+impl ParseOptions {
+    /// Creates the default options, which preserve values exactly as they appear in the input.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the character separating the integer and fractional part of floats, e.g. `,` to read `2,75`.
+    /// With a separator other than `.`, tokens containing a `.` are read as text. Defaults to `.`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the separator conflicts with the rest of the format,
+    /// i.e. if it is whitespace, a quote, a digit, a sign, an exponent marker or part of the key-value separator.
+    pub fn decimal_separator(mut self, separator: char) -> Self {
+        crate::check_separators(
+            separator,
+            &self.key_value_separator,
+            self.sequence_separator,
+        );
+        self.decimal_separator = separator;
+        self
+    }
+
+    /// Sets the separator between the key and the value of an item, e.g. `=>` to read `a=>1`. Defaults to `=`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the separator is empty, contains whitespace or quotes, or contains the decimal separator.
+    pub fn key_value_separator(mut self, separator: impl Into<String>) -> Self {
+        let separator = separator.into();
+        crate::check_separators(self.decimal_separator, &separator, self.sequence_separator);
+        self.key_value_separator = separator;
+        self
+    }
+
+    /// Sets the character separating the elements of sequence values, e.g. `,` to read `1,2,3`.
+    /// Unquoted values containing the separator are read as a [`Sequence`](ResultItem::Sequence).
+    /// A single trailing separator is ignored, so `1,2,3,` is read the same as `1,2,3`,
+    /// while empty elements anywhere else are read as [`Empty`](ResultItem::Empty), e.g. `1,,3`.
+    /// Sequences are not read by default.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the separator conflicts with the rest of the format,
+    /// i.e. if it is whitespace, a quote, a digit, a sign, the decimal separator or part of the key-value separator.
+    pub fn sequence_separator(mut self, separator: char) -> Self {
+        crate::check_separators(
+            self.decimal_separator,
+            &self.key_value_separator,
+            Some(separator),
+        );
+        self.sequence_separator = Some(separator);
+        self
+    }
+
+    /// Sets whether leading and trailing whitespace inside quoted values is removed.
+    /// Whitespace in the interior of a value is always kept. Disabled by default.
+    pub fn trim_quoted_values(mut self, trim: bool) -> Self {
+        self.trim_quoted_values = trim;
+        self
+    }
+}
 
 /// Parses a result line from a String into some target type.
 /// The target type can be something like a `HashMap<&str, ResultItem>` or a `Vec<(&str, ResultItem)>`.
+/// Items are separated by whitespace, so keys containing whitespace have to be quoted. The last item ends either at the end of the input or at trailing whitespace,
+/// both of which yield the same value.
+///
+/// Quoted values are always text. The kind of an unquoted value is determined by its whole token:
+/// `true` and `false` are booleans, digits with an optional sign are integers,
+/// and any other valid float is a float. This includes exponent notation (`1e9`, `-2.5e-3`), a leading `+`,
+/// a missing integer or fractional part (`.5` and `5.` are `0.5` and `5.0`) as well as `inf` and `nan`.
+/// All other unquoted values are text, e.g. `1.2.3` or `12abc`.
 ///
 /// # Arguments
 ///
@@ -26,6 +130,12 @@ use crate::ResultItem;
 /// use std::collections::HashMap;
 /// use serde_result_line::ResultItem;
 ///
+/// let map: HashMap<&str, ResultItem> = serde_result_line::from_string("RESULT a=.5 b=5. c=inf d=1.2.3").unwrap();
+/// assert_eq!(map["a"], ResultItem::Float(0.5));
+/// assert_eq!(map["b"], ResultItem::Float(5.0));
+/// assert_eq!(map["c"], ResultItem::Float(f64::INFINITY));
+/// assert_eq!(map["d"], ResultItem::from("1.2.3"));
+///
 /// let s = r#"RESULT a="some value" "a key"=12315 c=true"#;
 ///
 /// let map: HashMap<&str, ResultItem> = serde_result_line::from_string(s).unwrap();
@@ -34,61 +144,291 @@ use crate::ResultItem;
 /// expected.insert("a key", ResultItem::Integer(12315));
 /// expected.insert("c", ResultItem::Boolean(true));
 /// ```
-pub fn from_string<'a, Target>(input: &'a str) -> Result<Target, nom::error::Error<&str>>
+pub fn from_string<'a, Target>(input: &'a str) -> Result<Target, nom::error::Error<&'a str>>
 where
     Target: FromIterator<(&'a str, ResultItem)>,
 {
-    parse_result_line::<Target>(input)
+    from_string_with_options(input, &ParseOptions::default())
+}
+
+/// Parses a result line from a String into some target type, like [`from_string`], using the given options.
+///
+/// # Arguments
+///
+/// * `input`: The input result line to parse.
+/// * `options`: The options controlling how the line is parsed.
+///
+/// Returns: The data stored in the result line in form of the target type.
+pub fn from_string_with_options<'a, Target>(
+    input: &'a str,
+    options: &ParseOptions,
+) -> Result<Target, nom::error::Error<&'a str>>
+where
+    Target: FromIterator<(&'a str, ResultItem)>,
+{
+    parse_result_line::<Target>(input, options)
         .finish()
         .map(|(_, target)| target)
 }
 
+/// Parses a result line from a String into some target type, like [`from_string`],
+/// and extracts the schema version written by [`SerializeOptions::schema_version`](crate::SerializeOptions::schema_version).
+/// The schema version item is not part of the returned target.
+/// If the line contains several schema version items, the first one is the schema version.
+///
+/// # Arguments
+///
+/// * `input`: The input result line to parse.
+///
+/// Returns: The schema version, if the line contains one, and the remaining data stored in the result line
+/// in form of the target type.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+/// use serde_result_line::ResultItem;
+///
+/// let s = "RESULT __schema=2 a=1";
+///
+/// let (version, map): (_, HashMap<&str, ResultItem>) = serde_result_line::from_string_versioned(s).unwrap();
+/// assert_eq!(version, Some(2));
+/// assert_eq!(map.len(), 1);
+/// ```
+pub fn from_string_versioned<'a, Target>(
+    input: &'a str,
+) -> Result<(Option<u32>, Target), nom::error::Error<&'a str>>
+where
+    Target: FromIterator<(&'a str, ResultItem)>,
+{
+    let items: Vec<(&'a str, ResultItem)> = from_string(input)?;
+    let mut version = None;
+    let mut found = false;
+    let target = items
+        .into_iter()
+        .filter(|(key, value)| {
+            if *key != SCHEMA_VERSION_KEY {
+                return true;
+            }
+            if !found {
+                version = value.as_integer().and_then(|v| u32::try_from(v).ok());
+                found = true;
+            }
+            false
+        })
+        .collect();
+
+    Ok((version, target))
+}
+
+/// An error occurring when parsing a result line against an allowlist of keys.
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum AllowlistError<'a> {
+    #[error("{0}")]
+    Parse(nom::error::Error<&'a str>),
+    #[error("unexpected key \"{0}\"")]
+    UnexpectedKey(&'a str),
+}
+
+/// Parses a result line from a String into some target type, like [`from_string`],
+/// but only accepts items whose keys are contained in the allowlist.
+///
+/// # Arguments
+///
+/// * `input`: The input result line to parse.
+/// * `allowed`: The keys which are allowed to appear in the result line.
+///
+/// Returns: The data stored in the result line in form of the target type,
+/// or an error naming the first key which is not in the allowlist.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+/// use serde_result_line::{AllowlistError, ResultItem};
+///
+/// let s = "RESULT a=1 b=2 c=3";
+///
+/// let map: Result<HashMap<&str, ResultItem>, _> = serde_result_line::from_string_allowlist(s, &["a", "b"]);
+/// assert_eq!(map, Err(AllowlistError::UnexpectedKey("c")));
+/// ```
+pub fn from_string_allowlist<'a, Target>(
+    input: &'a str,
+    allowed: &[&str],
+) -> Result<Target, AllowlistError<'a>>
+where
+    Target: FromIterator<(&'a str, ResultItem)>,
+{
+    let items: Vec<(&'a str, ResultItem)> = from_string(input).map_err(AllowlistError::Parse)?;
+    if let Some((key, _)) = items.iter().find(|(key, _)| !allowed.contains(key)) {
+        return Err(AllowlistError::UnexpectedKey(key));
+    }
+    Ok(items.into_iter().collect())
+}
+
+/// Parses all result lines contained in a multi-line input, like the output of a program.
+/// Lines which do not start with the `RESULT` tag followed by at least one item are skipped,
+/// such as log output like `RESULT is done`. Leading whitespace before the tag is allowed
+/// and any trailing text after the last valid item of a line is ignored.
+///
+/// # Arguments
+///
+/// * `input`: The input containing result lines.
+///
+/// Returns: An iterator over the data stored in each result line in form of the target type.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+/// use serde_result_line::ResultItem;
+///
+/// let s = "starting benchmark\nRESULT a=1\nfinished\n  RESULT a=2";
+///
+/// let maps: Vec<HashMap<&str, ResultItem>> = serde_result_line::from_lines(s).collect();
+/// assert_eq!(maps.len(), 2);
+/// assert_eq!(maps[1]["a"], ResultItem::Integer(2));
+/// ```
+pub fn from_lines<'a, Target>(input: &'a str) -> impl Iterator<Item = Target> + 'a
+where
+    Target: FromIterator<(&'a str, ResultItem)>,
+{
+    input
+        .lines()
+        .map(str::trim_start)
+        // Parsing only fails for lines without the `RESULT` tag
+        .filter_map(|line| {
+            parse_result_line::<Vec<(&'a str, ResultItem)>>(line, &ParseOptions::default()).ok()
+        })
+        .filter(|(_, items)| !items.is_empty())
+        .map(|(_, items)| items.into_iter().collect())
+}
+
 fn parse_delimited_string<'a>() -> impl FnMut(&'a str) -> IResult<&'a str, &'a str> {
     delimited(
         nom::character::complete::char('"'),
-        take_until1("\""),
+        take_until("\""),
         nom::character::complete::char('"'),
     )
 }
 
-fn parse_key<'a>() -> impl Parser<&'a str, &'a str, nom::error::Error<&'a str>> {
-    nom::branch::alt((parse_delimited_string(), take_until1("=")))
+/// Parses a key, which has to be quoted if it contains whitespace.
+fn parse_key<'a>(input: &'a str, separator: &str) -> IResult<&'a str, &'a str> {
+    let unquoted = nom::combinator::verify(take_until1(separator), |key: &str| {
+        !key.contains(char::is_whitespace)
+    });
+    nom::branch::alt((parse_delimited_string(), unquoted))(input)
+}
+
+/// Recognizes the end of a value without consuming it.
+/// A value ends at the whitespace separating it from the next item or at the end of the input.
+/// Any whitespace ends a value, including line breaks, matching where unquoted tokens end.
+fn value_end(input: &str) -> IResult<&str, &str> {
+    peek(nom::branch::alt((
+        nom::bytes::complete::take_while1(char::is_whitespace),
+        eof,
+    )))(input)
 }
 
-fn parse_value(input: &str) -> IResult<&str, ResultItem> {
+fn parse_value<'a>(input: &'a str, options: &ParseOptions) -> IResult<&'a str, ResultItem> {
     use nom::branch::alt;
+    let mut parser = alt((
+        value_end.map(|_| ResultItem::Empty),
+        terminated(parse_delimited_string(), value_end)
+            .map(|s| {
+                if options.trim_quoted_values {
+                    s.trim()
+                } else {
+                    s
+                }
+            })
+            .map(ResultItem::from),
+        take_till1(char::is_whitespace).map(|token| parse_sequence_or_token(token, options)),
+    ));
+
+    parser.parse(input)
+}
+
+/// Determines the value of an unquoted token, which is a sequence if it contains the sequence separator.
+/// A single trailing separator does not start another element.
+fn parse_sequence_or_token(token: &str, options: &ParseOptions) -> ResultItem {
+    let Some(separator) = options
+        .sequence_separator
+        .filter(|&sep| token.contains(sep))
+    else {
+        return parse_token(token, options);
+    };
+    let elements = token.strip_suffix(separator).unwrap_or(token);
+    let items = elements
+        .split(separator)
+        .map(|element| match element {
+            "" => ResultItem::Empty,
+            element => parse_token(element, options),
+        })
+        .collect();
+    ResultItem::Sequence(items)
+}
+
+/// Determines the value of an unquoted token, which spans the whole value.
+/// A token is a boolean if it is `true` or `false`, an integer if it consists only of digits with an optional sign,
+/// and a float if it is any other valid float, including exponent notation (`1e9`, `-2.5e-3`), a leading `+`,
+/// a missing integer or fractional part (`.5`, `5.`) as well as `inf` and `nan`.
+/// All other tokens are text.
+fn parse_token(token: &str, options: &ParseOptions) -> ResultItem {
+    use nom::branch::alt;
+    use nom::combinator::all_consuming;
     let tag = nom::bytes::complete::tag::<&str, &str, nom::error::Error<&str>>;
     let mut parser = alt((
-        parse_delimited_string().map(ResultItem::from),
-        alt((tag("true"), tag("false")))
+        all_consuming(alt((tag("true"), tag("false"))))
             .map(|s| bool::from_str(s).unwrap())
             .map(ResultItem::from),
-        terminated(nom::character::complete::i64, peek(space1))
-            .map(|i| i as isize)
-            .map(ResultItem::from),
-        nom::number::complete::double.map(ResultItem::from),
-        parse_key().map(ResultItem::from),
+        all_consuming(nom::character::complete::i128).map(ResultItem::from),
+        all_consuming(nom::character::complete::u128).map(ResultItem::from),
     ));
+    if let Ok((_, item)) = parser.parse(token) {
+        return item;
+    }
 
-    parser.parse(input)
+    let float = match options.decimal_separator {
+        '.' => Some(token.to_owned()),
+        _ if token.contains('.') => None,
+        separator => Some(token.replacen(separator, ".", 1)),
+    };
+    float
+        .and_then(|float| {
+            all_consuming(nom::number::complete::double::<_, nom::error::Error<&str>>)(&float)
+                .map(|(_, f)| f)
+                .ok()
+        })
+        .map(ResultItem::from)
+        .unwrap_or_else(|| ResultItem::from(token))
 }
 
-fn parse_named_item(input: &str) -> IResult<&str, (&str, ResultItem)> {
+/// Checks whether the given text is read back as the same text value when written without quotes.
+pub(crate) fn is_bare_text(text: &str, options: &ParseOptions) -> bool {
+    matches!(parse_value(text, options), Ok(("", ResultItem::Text(t))) if t == text)
+}
+
+fn parse_named_item<'a>(
+    input: &'a str,
+    options: &ParseOptions,
+) -> IResult<&'a str, (&'a str, ResultItem)> {
+    let separator = options.key_value_separator.as_str();
     separated_pair(
-        parse_key(),
-        nom::character::complete::char('='),
-        parse_value,
+        |i| parse_key(i, separator),
+        nom::bytes::complete::tag(separator),
+        |i| parse_value(i, options),
     )
     .parse(input)
 }
 
-fn parse_result_line<'a, Target>(input: &'a str) -> IResult<&'a str, Target>
+fn parse_result_line<'a, Target>(input: &'a str, options: &ParseOptions) -> IResult<&'a str, Target>
 where
     Target: FromIterator<(&'a str, ResultItem)>,
 {
     let tag = nom::bytes::complete::tag::<&str, &str, nom::error::Error<&str>>;
     let (mut input, _) = tag("RESULT")(input)?;
-    let mut named_item_parser = preceded(space1, parse_named_item);
+    let mut named_item_parser = preceded(space1, |i| parse_named_item(i, options));
     // Create an iterator parsing all item pairs
     let pairs = std::iter::from_fn(|| match named_item_parser(input) {
         Ok((rest, pair)) => {
@@ -108,13 +448,16 @@ where
 mod test {
     use std::collections::HashMap;
 
+    use super::ParseOptions;
     use crate::ResultItem;
 
     #[test]
     fn test() {
         const S: &str =
             r#"RESULT a="hello world" b=-123423904 "a key"=8123 nowhitespace=8123.23 d=true"#;
-        let map = super::parse_result_line::<HashMap<&str, ResultItem>>(S).map(|(_, map)| map);
+        let map =
+            super::parse_result_line::<HashMap<&str, ResultItem>>(S, &ParseOptions::default())
+                .map(|(_, map)| map);
 
         let mut expected = HashMap::<&str, ResultItem>::new();
         expected.insert("a", ResultItem::Text("hello world".to_owned()));
@@ -125,4 +468,331 @@ mod test {
 
         assert_eq!(Ok(expected), map, "Parsed map does not match expected map");
     }
+
+    #[test]
+    fn trailing_whitespace() {
+        let without = super::parse_result_line::<HashMap<&str, ResultItem>>(
+            "RESULT a=5",
+            &ParseOptions::default(),
+        )
+        .map(|(_, map)| map);
+        let with = super::parse_result_line::<HashMap<&str, ResultItem>>(
+            "RESULT a=5 ",
+            &ParseOptions::default(),
+        )
+        .map(|(_, map)| map);
+
+        let mut expected = HashMap::<&str, ResultItem>::new();
+        expected.insert("a", ResultItem::Integer(5));
+
+        assert_eq!(Ok(expected), without);
+        assert_eq!(
+            without, with,
+            "Trailing whitespace changed the parsed items"
+        );
+
+        // Line breaks end a value just like spaces
+        for line_break in ["\n", "\r\n"] {
+            let input = format!(r#"RESULT a="x y" b=1 c={line_break}"#);
+            let map = super::parse_result_line::<HashMap<&str, ResultItem>>(
+                &input,
+                &ParseOptions::default(),
+            )
+            .map(|(_, map)| map);
+
+            let mut expected = HashMap::<&str, ResultItem>::new();
+            expected.insert("a", ResultItem::from("x y"));
+            expected.insert("b", ResultItem::Integer(1));
+            expected.insert("c", ResultItem::Empty);
+            assert_eq!(Ok(expected), map, "{line_break:?} changed the parsed items");
+
+            let input = format!(r#"RESULT a="x"{line_break}"#);
+            let map: HashMap<&str, ResultItem> = crate::from_string(&input).unwrap();
+            assert_eq!(map["a"], ResultItem::from("x"));
+        }
+
+        let map = super::parse_result_line::<HashMap<&str, ResultItem>>(
+            "RESULT a=5.5 b=truthy c=text d=1x",
+            &ParseOptions::default(),
+        )
+        .map(|(_, map)| map);
+
+        let mut expected = HashMap::<&str, ResultItem>::new();
+        expected.insert("a", ResultItem::Float(5.5));
+        expected.insert("b", ResultItem::from("truthy"));
+        expected.insert("c", ResultItem::from("text"));
+        expected.insert("d", ResultItem::from("1x"));
+
+        assert_eq!(Ok(expected), map);
+    }
+
+    #[test]
+    fn floats() {
+        let map = super::parse_result_line::<HashMap<&str, ResultItem>>(
+            "RESULT a=1e9 b=-2.5e-3 c=+4.0 d=.5 e=5. f=+7 g=1E3 h=1e i=1.2.3 j=-",
+            &ParseOptions::default(),
+        )
+        .map(|(_, map)| map);
+
+        let mut expected = HashMap::<&str, ResultItem>::new();
+        expected.insert("a", ResultItem::Float(1e9));
+        expected.insert("b", ResultItem::Float(-2.5e-3));
+        expected.insert("c", ResultItem::Float(4.0));
+        expected.insert("d", ResultItem::Float(0.5));
+        expected.insert("e", ResultItem::Float(5.0));
+        expected.insert("f", ResultItem::Integer(7));
+        expected.insert("g", ResultItem::Float(1e3));
+        expected.insert("h", ResultItem::from("1e"));
+        expected.insert("i", ResultItem::from("1.2.3"));
+        expected.insert("j", ResultItem::from("-"));
+
+        assert_eq!(Ok(expected), map);
+    }
+
+    #[test]
+    fn decimal_separator() {
+        let options = ParseOptions::new().decimal_separator(',');
+        let map = super::parse_result_line::<HashMap<&str, ResultItem>>(
+            "RESULT a=2,75 b=-2,5e3 c=12 d=2.75 e=1,2,3",
+            &options,
+        )
+        .map(|(_, map)| map);
+
+        let mut expected = HashMap::<&str, ResultItem>::new();
+        expected.insert("a", ResultItem::Float(2.75));
+        expected.insert("b", ResultItem::Float(-2500.0));
+        expected.insert("c", ResultItem::Integer(12));
+        expected.insert("d", ResultItem::from("2.75"));
+        expected.insert("e", ResultItem::from("1,2,3"));
+
+        assert_eq!(Ok(expected), map);
+    }
+
+    #[test]
+    #[should_panic]
+    fn conflicting_decimal_separator() {
+        let _ = ParseOptions::new().decimal_separator(' ');
+    }
+
+    #[test]
+    fn key_value_separator() {
+        let options = ParseOptions::new().key_value_separator("=>");
+        let map = super::parse_result_line::<HashMap<&str, ResultItem>>(
+            r#"RESULT a=>1 b=c=>x=>y "d e"=>true"#,
+            &options,
+        )
+        .map(|(_, map)| map);
+
+        let mut expected = HashMap::<&str, ResultItem>::new();
+        expected.insert("a", ResultItem::Integer(1));
+        expected.insert("b=c", ResultItem::from("x=>y"));
+        expected.insert("d e", ResultItem::Boolean(true));
+
+        assert_eq!(Ok(expected), map);
+    }
+
+    #[test]
+    #[should_panic]
+    fn conflicting_key_value_separator() {
+        let _ = ParseOptions::new()
+            .decimal_separator(',')
+            .key_value_separator(":,");
+    }
+
+    #[test]
+    fn empty_text() {
+        let map = super::parse_result_line::<HashMap<&str, ResultItem>>(
+            r#"RESULT a="" b= c="""#,
+            &ParseOptions::default(),
+        )
+        .map(|(_, map)| map);
+
+        let mut expected = HashMap::<&str, ResultItem>::new();
+        expected.insert("a", ResultItem::from(""));
+        expected.insert("b", ResultItem::Empty);
+        expected.insert("c", ResultItem::from(""));
+
+        assert_eq!(Ok(expected), map);
+    }
+
+    #[test]
+    fn empty_values() {
+        let map = super::parse_result_line::<HashMap<&str, ResultItem>>(
+            "RESULT a= b=1 c=",
+            &ParseOptions::default(),
+        )
+        .map(|(_, map)| map);
+
+        let mut expected = HashMap::<&str, ResultItem>::new();
+        expected.insert("a", ResultItem::Empty);
+        expected.insert("b", ResultItem::Integer(1));
+        expected.insert("c", ResultItem::Empty);
+
+        assert_eq!(Ok(expected), map);
+    }
+
+    #[test]
+    fn quoted_values_are_text() {
+        let map = super::parse_result_line::<HashMap<&str, ResultItem>>(
+            r#"RESULT a="true" b="123" c="1.5" d=true"#,
+            &ParseOptions::default(),
+        )
+        .map(|(_, map)| map);
+
+        let mut expected = HashMap::<&str, ResultItem>::new();
+        expected.insert("a", ResultItem::from("true"));
+        expected.insert("b", ResultItem::from("123"));
+        expected.insert("c", ResultItem::from("1.5"));
+        expected.insert("d", ResultItem::Boolean(true));
+
+        assert_eq!(Ok(expected), map);
+    }
+
+    #[test]
+    fn allowlist() {
+        use super::AllowlistError;
+
+        let map = super::from_string_allowlist::<HashMap<&str, ResultItem>>(
+            "RESULT a=1 b=text",
+            &["a", "b", "c"],
+        );
+        let mut expected = HashMap::<&str, ResultItem>::new();
+        expected.insert("a", ResultItem::Integer(1));
+        expected.insert("b", ResultItem::from("text"));
+        assert_eq!(Ok(expected), map);
+
+        let map = super::from_string_allowlist::<HashMap<&str, ResultItem>>(
+            "RESULT a=1 extra=2 b=text other=3",
+            &["a", "b", "c"],
+        );
+        assert_eq!(Err(AllowlistError::UnexpectedKey("extra")), map);
+    }
+
+    #[test]
+    fn multiple_lines() {
+        const S: &str = "compiling...
+RESULT a=1 b=\"hello world\"
+some log output mentioning RESULT a=3
+   RESULT a=2 b=text trailing noise
+RESULTS are in
+RESULT is done
+RESULT
+RESULT a=3 took 5ms size=4
+";
+        let maps: Vec<HashMap<&str, ResultItem>> = super::from_lines(S).collect();
+
+        let mut first = HashMap::<&str, ResultItem>::new();
+        first.insert("a", ResultItem::Integer(1));
+        first.insert("b", ResultItem::from("hello world"));
+        let mut second = HashMap::<&str, ResultItem>::new();
+        second.insert("a", ResultItem::Integer(2));
+        second.insert("b", ResultItem::from("text"));
+        // Trailing text is ignored even if it contains a key-value separator
+        let mut third = HashMap::<&str, ResultItem>::new();
+        third.insert("a", ResultItem::Integer(3));
+
+        assert_eq!(vec![first, second, third], maps);
+    }
+
+    #[test]
+    fn trim_quoted_values() {
+        const S: &str = r#"RESULT a=" padded " b=" inner  space ""#;
+
+        let map = super::parse_result_line::<HashMap<&str, ResultItem>>(S, &ParseOptions::new())
+            .map(|(_, map)| map);
+        let mut expected = HashMap::<&str, ResultItem>::new();
+        expected.insert("a", ResultItem::from(" padded "));
+        expected.insert("b", ResultItem::from(" inner  space "));
+        assert_eq!(Ok(expected), map);
+
+        let options = ParseOptions::new().trim_quoted_values(true);
+        let map =
+            super::parse_result_line::<HashMap<&str, ResultItem>>(S, &options).map(|(_, map)| map);
+        let mut expected = HashMap::<&str, ResultItem>::new();
+        expected.insert("a", ResultItem::from("padded"));
+        expected.insert("b", ResultItem::from("inner  space"));
+        assert_eq!(Ok(expected), map);
+    }
+
+    #[test]
+    fn wide_integers() {
+        const S: &str = "RESULT a=340282366920938463463374607431768211455 b=-170141183460469231731687303715884105728 c=-12";
+        let map =
+            super::parse_result_line::<HashMap<&str, ResultItem>>(S, &ParseOptions::default())
+                .map(|(_, map)| map);
+
+        let mut expected = HashMap::<&str, ResultItem>::new();
+        expected.insert("a", ResultItem::UInteger128(u128::MAX));
+        expected.insert("b", ResultItem::Integer128(i128::MIN));
+        expected.insert("c", ResultItem::Integer(-12));
+
+        assert_eq!(Ok(expected), map, "Parsed map does not match expected map");
+
+        // A wide integer ending the line must not be cut short by the end of the input
+        let map = super::parse_result_line::<HashMap<&str, ResultItem>>(
+            "RESULT hash=340282366920938463463374607431768211455",
+            &ParseOptions::default(),
+        )
+        .map(|(_, map)| map);
+        let mut expected = HashMap::<&str, ResultItem>::new();
+        expected.insert("hash", ResultItem::UInteger128(u128::MAX));
+        assert_eq!(Ok(expected), map);
+    }
+
+    #[test]
+    fn sequences() {
+        const S: &str = "RESULT a=1,2.5,x b=1,2,3, c=1,,3 d=1,2";
+        let options = ParseOptions::new().sequence_separator(',');
+        let map =
+            super::parse_result_line::<HashMap<&str, ResultItem>>(S, &options).map(|(_, map)| map);
+
+        let mut expected = HashMap::<&str, ResultItem>::new();
+        expected.insert(
+            "a",
+            ResultItem::Sequence(vec![
+                ResultItem::Integer(1),
+                ResultItem::Float(2.5),
+                ResultItem::from("x"),
+            ]),
+        );
+        // A trailing separator does not add an empty element
+        expected.insert(
+            "b",
+            ResultItem::Sequence(vec![
+                ResultItem::Integer(1),
+                ResultItem::Integer(2),
+                ResultItem::Integer(3),
+            ]),
+        );
+        expected.insert(
+            "c",
+            ResultItem::Sequence(vec![
+                ResultItem::Integer(1),
+                ResultItem::Empty,
+                ResultItem::Integer(3),
+            ]),
+        );
+        expected.insert(
+            "d",
+            ResultItem::Sequence(vec![ResultItem::Integer(1), ResultItem::Integer(2)]),
+        );
+        assert_eq!(Ok(expected), map);
+
+        // Without a sequence separator, the values are text
+        let map =
+            super::parse_result_line::<HashMap<&str, ResultItem>>(S, &ParseOptions::default())
+                .map(|(_, map)| map);
+        assert_eq!(map.unwrap()["b"], ResultItem::from("1,2,3,"));
+    }
+
+    #[test]
+    fn duplicate_schema_version() {
+        let (version, map): (_, HashMap<&str, ResultItem>) =
+            super::from_string_versioned("RESULT __schema=1 a=1 __schema=5").unwrap();
+        assert_eq!(version, Some(1));
+
+        let mut expected = HashMap::<&str, ResultItem>::new();
+        expected.insert("a", ResultItem::Integer(1));
+        assert_eq!(map, expected);
+    }
 }
@@ -0,0 +1,98 @@
+//! Conversion of flat JSON objects into result lines
+
+use serde_json::Value;
+
+use crate::{ser::join_items, Erra, NamedItem, ResultItem, SerializeOptions};
+
+/// Takes a JSON object and turns its top-level fields into a result line.
+/// Only flat objects are supported, meaning that fields containing objects or arrays result in an error.
+/// Fields which are `null` are ignored in the output.
+///
+/// # Arguments
+///
+/// * `value`: The JSON object to convert
+///
+/// Returns: The object's fields as a result line.
+///
+/// # Examples
+///
+/// ```
+/// let value = serde_json::json!({ "a": "hello world", "b": -12, "c": 1.5, "d": null });
+///
+/// assert_eq!(
+///     serde_result_line::to_string_from_json(&value),
+///     Ok(r#"RESULT a="hello world" b=-12 c=1.5"#.to_string())
+/// );
+/// ```
+pub fn to_string_from_json(value: &Value) -> Result<String, Erra> {
+    let Value::Object(object) = value else {
+        return Err(Erra::Unsupported("non-object json value"));
+    };
+
+    let mut items = Vec::with_capacity(object.len());
+    for (key, value) in object {
+        let value = match value {
+            Value::Null => continue,
+            Value::Bool(b) => ResultItem::from(*b),
+            Value::Number(n) => {
+                if let Some(i) = n.as_i64() {
+                    ResultItem::from(i as i128)
+                } else if let Some(u) = n.as_u64() {
+                    ResultItem::from(u as u128)
+                } else {
+                    ResultItem::from(n.as_f64().unwrap_or(f64::NAN))
+                }
+            }
+            Value::String(s) => ResultItem::from(s.as_str()),
+            Value::Array(_) => return Err(Erra::Unsupported("json array")),
+            Value::Object(_) => return Err(Erra::Unsupported("nested json object")),
+        };
+        items.push(NamedItem::new(key.as_str(), value));
+    }
+
+    join_items(items, &SerializeOptions::default())
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use crate::Erra;
+
+    #[test]
+    fn flat_object() {
+        let value = json!({
+            "a": "hello world",
+            "b": -123423904,
+            "a key": 8123,
+            "c": 8123.25,
+            "d": true,
+            "e": null,
+        });
+
+        assert_eq!(
+            super::to_string_from_json(&value),
+            Ok(r#"RESULT a="hello world" "a key"=8123 b=-123423904 c=8123.25 d=true"#.to_string())
+        );
+        assert_eq!(
+            super::to_string_from_json(&json!({ "a": 1.0 })),
+            Ok("RESULT a=1.0".to_string())
+        );
+    }
+
+    #[test]
+    fn nested_values() {
+        assert_eq!(
+            super::to_string_from_json(&json!({ "a": { "b": 1 } })),
+            Err(Erra::Unsupported("nested json object"))
+        );
+        assert_eq!(
+            super::to_string_from_json(&json!({ "a": [1, 2] })),
+            Err(Erra::Unsupported("json array"))
+        );
+        assert_eq!(
+            super::to_string_from_json(&json!([1, 2])),
+            Err(Erra::Unsupported("non-object json value"))
+        );
+    }
+}
@@ -8,23 +8,85 @@
 //! It also provides a (non-serde) deserializer which can create any type which implements [`FromIterator`]
 //! for iterators over items of `(&str, ResultItem)`,
 //! like `HashMap<&str, ResultItem>` or `Vec<(&str, ResultItem)>`.
+//!
+//! With the `json` feature enabled, flat [serde_json](https://docs.rs/serde_json/latest/serde_json/) objects
+//! can be turned into result lines directly.
 
 use serde::Serialize;
+use std::cmp::Ordering;
 use std::fmt::Display;
+use std::hash::{Hash, Hasher};
 
-pub use de::from_string;
-pub use ser::to_string;
+pub use de::{
+    from_lines, from_string, from_string_allowlist, from_string_versioned,
+    from_string_with_options, AllowlistError, ParseOptions,
+};
+#[cfg(feature = "json")]
+pub use json::to_string_from_json;
+pub use line::{LineError, ResultLine};
+pub use ser::{
+    to_string, to_string_map, to_string_measurements, to_string_sorted, to_string_with_options,
+    Erra, ExtraColumns, FloatFormat, SerializeOptions,
+};
 
 mod de;
+#[cfg(feature = "json")]
+mod json;
+mod line;
 mod ser;
 
+/// Panics if the separators conflict with each other or would be confused with another part of a result line.
+pub(crate) fn check_separators(
+    decimal_separator: char,
+    key_value_separator: &str,
+    sequence_separator: Option<char>,
+) {
+    assert!(
+        !(decimal_separator.is_whitespace()
+            || decimal_separator.is_ascii_digit()
+            || matches!(decimal_separator, '"' | '+' | '-' | 'e' | 'E')
+            || key_value_separator.contains(decimal_separator)),
+        "{decimal_separator:?} cannot be used as a decimal separator"
+    );
+    assert!(
+        !(key_value_separator.is_empty()
+            || key_value_separator.contains(|c: char| c.is_whitespace() || c == '"')),
+        "{key_value_separator:?} cannot be used as a key-value separator"
+    );
+    if let Some(sequence_separator) = sequence_separator {
+        assert!(
+            !(sequence_separator.is_whitespace()
+                || sequence_separator.is_ascii_digit()
+                || matches!(sequence_separator, '"' | '+' | '-')
+                || sequence_separator == decimal_separator
+                || key_value_separator.contains(sequence_separator)),
+            "{sequence_separator:?} cannot be used as a sequence separator"
+        );
+    }
+}
+
+/// The name of the item holding the schema version of a result line, see [`SerializeOptions::schema_version`].
+pub const SCHEMA_VERSION_KEY: &str = "__schema";
+
 /// An enum representing the possible types a result item's value can be.
-#[derive(Debug, Serialize, Clone, PartialEq, Default)]
+///
+/// Result items are totally ordered, so they can be sorted and used as keys of maps.
+/// Items of different kinds are ordered as follows:
+/// [`Empty`](ResultItem::Empty) < booleans < integers < floats < characters < text < sequences < named items.
+/// Integers are compared by their numeric value, regardless of their variant.
+/// Floats are compared using [`f64::total_cmp`], which means that `-0.0` is less than `0.0`
+/// and that `NaN` is equal to itself.
+/// Sequences are compared element by element.
+#[derive(Debug, Serialize, Clone, Default)]
 pub enum ResultItem {
     /// A named item, e.g. `a="some value"`
     Named(Box<NamedItem>),
     /// An integer, e.g. `123`
     Integer(isize),
+    /// An integer which does not fit into an [`isize`], e.g. `-170141183460469231731687303715884105728`
+    Integer128(i128),
+    /// An unsigned integer which does not fit into an [`i128`], e.g. `340282366920938463463374607431768211455`
+    UInteger128(u128),
     /// A float, e.g. `123.456`
     Float(f64),
     /// A boolean, e.g. `true`
@@ -33,28 +95,124 @@ pub enum ResultItem {
     Character(char),
     /// A text string, e.g. `"some value"`
     Text(String),
+    /// A sequence of values, e.g. `1,2,3`, see [`SerializeOptions::sequence_separator`].
+    /// Without a sequence separator, its elements are written as separate items, e.g. `a_0=1 a_1=2 a_2=3`
+    Sequence(Vec<ResultItem>),
     /// An empty item
     #[default]
+    // Serialized as a unit, so that it stays empty instead of being written as the variant name
+    #[serde(serialize_with = "serialize_empty")]
     Empty,
 }
 
+fn serialize_empty<S: serde::Serializer>(serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_unit()
+}
+
+impl ResultItem {
+    /// The position of this item's kind in the order of result items.
+    fn rank(&self) -> u8 {
+        match self {
+            Self::Empty => 0,
+            Self::Boolean(_) => 1,
+            Self::Integer(_) | Self::Integer128(_) | Self::UInteger128(_) => 2,
+            Self::Float(_) => 3,
+            Self::Character(_) => 4,
+            Self::Text(_) => 5,
+            Self::Sequence(_) => 6,
+            Self::Named(_) => 7,
+        }
+    }
+
+    /// A key ordering all integer variants by their value.
+    /// Negative values come first and their two's complement preserves their order.
+    fn integer_key(&self) -> Option<(bool, u128)> {
+        match *self {
+            Self::Integer(i) => Some((i >= 0, i as i128 as u128)),
+            Self::Integer128(i) => Some((i >= 0, i as u128)),
+            Self::UInteger128(u) => Some((true, u)),
+            _ => None,
+        }
+    }
+}
+
+impl PartialEq for ResultItem {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for ResultItem {}
+
+impl PartialOrd for ResultItem {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ResultItem {
+    fn cmp(&self, other: &Self) -> Ordering {
+        use ResultItem as E;
+        match (self, other) {
+            (E::Named(a), E::Named(b)) => a.cmp(b),
+            (E::Float(a), E::Float(b)) => a.total_cmp(b),
+            (E::Boolean(a), E::Boolean(b)) => a.cmp(b),
+            (E::Character(a), E::Character(b)) => a.cmp(b),
+            (E::Text(a), E::Text(b)) => a.cmp(b),
+            (E::Sequence(a), E::Sequence(b)) => a.cmp(b),
+            _ => match (self.integer_key(), other.integer_key()) {
+                (Some(a), Some(b)) => a.cmp(&b),
+                _ => self.rank().cmp(&other.rank()),
+            },
+        }
+    }
+}
+
+impl Hash for ResultItem {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        use ResultItem as E;
+        self.rank().hash(state);
+        match self {
+            E::Named(item) => item.hash(state),
+            E::Integer(_) | E::Integer128(_) | E::UInteger128(_) => self.integer_key().hash(state),
+            E::Float(f) => f.to_bits().hash(state),
+            E::Boolean(b) => b.hash(state),
+            E::Character(c) => c.hash(state),
+            E::Text(t) => t.hash(state),
+            E::Sequence(items) => items.hash(state),
+            E::Empty => {}
+        }
+    }
+}
+
 impl Display for ResultItem {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         use ResultItem as E;
         match self {
             E::Named(item) => write!(f, "{item}"),
             E::Integer(item) => write!(f, "{item}"),
+            E::Integer128(item) => write!(f, "{item}"),
+            E::UInteger128(item) => write!(f, "{item}"),
             E::Float(item) => write!(f, "{item}"),
             E::Boolean(item) => write!(f, "{item}"),
             E::Character(item) => write!(f, "{item}"),
             E::Text(item) => write!(f, "{item}"),
+            E::Sequence(items) => {
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{item}")?;
+                }
+                Ok(())
+            }
             E::Empty => write!(f, ""),
         }
     }
 }
 
 /// A named item, e.g. `a="some value"`
-#[derive(Debug, Serialize, Default, Clone, PartialEq)]
+#[derive(Debug, Serialize, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct NamedItem {
     /// The name of the item
     name: ResultItem,
@@ -69,22 +227,116 @@ impl NamedItem {
             value: value.into(),
         }
     }
+
+    /// The name of the item
+    pub fn name(&self) -> &ResultItem {
+        &self.name
+    }
+
+    /// The value of the item
+    pub fn value(&self) -> &ResultItem {
+        &self.value
+    }
+
+    /// Checks whether the item has the given name.
+    pub(crate) fn has_name(&self, key: &str) -> bool {
+        match &self.name {
+            ResultItem::Text(name) => name == key,
+            name => name.to_string() == key,
+        }
+    }
 }
 
 impl Display for NamedItem {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match &self.name {
-            ResultItem::Text(t) if t.contains(|c: char| c.is_whitespace()) => {
-                write!(f, "\"{t}\"=")?
+        self.write(f, &SerializeOptions::default())
+    }
+}
+
+impl NamedItem {
+    /// Writes this item as it appears in a result line, formatted according to the given options.
+    pub(crate) fn write(
+        &self,
+        f: &mut impl std::fmt::Write,
+        options: &SerializeOptions,
+    ) -> std::fmt::Result {
+        match (&self.value, options.sequence_separator) {
+            // Without a separator, each element is written as a separate item, like a serialized sequence
+            (ResultItem::Sequence(items), None) if !items.is_empty() => {
+                let name = self.name.to_string();
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(' ')?;
+                    }
+                    write_key(f, &format!("{name}_{i}"), options)?;
+                    write_value(f, item, options)?;
+                }
+                return Ok(());
             }
-            _ => write!(f, "{}=", &self.name)?,
+            _ => {}
+        }
+        match &self.name {
+            ResultItem::Text(name) => write_key(f, name, options)?,
+            name => write_key(f, &name.to_string(), options)?,
         }
         match &self.value {
-            ResultItem::Text(t) if t.contains(|c: char| c.is_whitespace()) => {
-                write!(f, "\"{t}\"")
+            ResultItem::Sequence(items) => {
+                let separator = options.sequence_separator.unwrap_or(',');
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "{separator}")?;
+                    }
+                    write_value(f, item, options)?;
+                }
+                Ok(())
+            }
+            value => write_value(f, value, options),
+        }
+    }
+}
+
+/// Writes a key followed by the key-value separator, quoting the key if necessary.
+pub(crate) fn write_key(
+    f: &mut impl std::fmt::Write,
+    name: &str,
+    options: &SerializeOptions,
+) -> std::fmt::Result {
+    let separator = &options.key_value_separator;
+    // A key without the first character of the separator cannot contain a partial separator
+    let separator_start = separator.chars().next().unwrap_or('=');
+    if name.contains(|c: char| c.is_whitespace() || c == '=' || c == separator_start) {
+        f.write_char('"')?;
+        f.write_str(name)?;
+        f.write_char('"')?;
+    } else {
+        f.write_str(name)?;
+    }
+    f.write_str(separator)
+}
+
+/// Writes a single value as it appears in a result line, formatted according to the given options.
+pub(crate) fn write_value(
+    f: &mut impl std::fmt::Write,
+    value: &ResultItem,
+    options: &SerializeOptions,
+) -> std::fmt::Result {
+    match value {
+        // Quote any text which would otherwise be read back as a different kind of value, e.g. `"true"`
+        ResultItem::Text(t) if !de::is_bare_text(t, &options.parse_options()) => {
+            write!(f, "\"{t}\"")
+        }
+        // Characters are read back as text, so they are written like text of a single character
+        ResultItem::Character(c) => write_value(f, &ResultItem::Text(c.to_string()), options),
+        ResultItem::Float(v) if options.writes_floats_as_display() => {
+            write!(f, "{v}")?;
+            // Finite floats are displayed without an exponent, so whole numbers have no decimal point yet
+            if v.is_finite() && v.fract() == 0.0 {
+                f.write_str(".0")?;
             }
-            _ => write!(f, "{}", &self.value),
+            Ok(())
         }
+        ResultItem::Float(v) => write!(f, "{}", options.format_float(*v)),
+        _ => write!(f, "{value}"),
     }
 }
 
@@ -92,11 +344,75 @@ impl ResultItem {
     pub fn is_empty(&self) -> bool {
         matches!(self, Self::Empty)
     }
+
+    /// Returns the value of an [`Integer`](ResultItem::Integer), or `None` for any other variant.
+    pub fn as_integer(&self) -> Option<isize> {
+        match self {
+            Self::Integer(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of a [`Float`](ResultItem::Float), or `None` for any other variant.
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            Self::Float(f) => Some(*f),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of a [`Boolean`](ResultItem::Boolean), or `None` for any other variant.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Boolean(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of a [`Character`](ResultItem::Character), or `None` for any other variant.
+    pub fn as_char(&self) -> Option<char> {
+        match self {
+            Self::Character(c) => Some(*c),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of a [`Text`](ResultItem::Text), or `None` for any other variant.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::Text(t) => Some(t),
+            _ => None,
+        }
+    }
+
+    /// Renders the item as a single CSV field.
+    /// Fields containing commas, quotes or line breaks are enclosed in quotes, with inner quotes doubled.
+    /// [`Empty`](ResultItem::Empty) items are rendered as a blank field.
+    pub fn to_csv_field(&self) -> String {
+        let field = self.to_string();
+        if field.contains([',', '"', '\n', '\r']) {
+            format!("\"{}\"", field.replace('"', "\"\""))
+        } else {
+            field
+        }
+    }
+
+    /// Returns the value of any numeric variant as an [`f64`], or `None` for any other variant.
+    /// Integers with a magnitude above 2<sup>53</sup> lose precision in the conversion.
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            Self::Integer(i) => Some(*i as f64),
+            Self::Integer128(i) => Some(*i as f64),
+            Self::UInteger128(u) => Some(*u as f64),
+            Self::Float(f) => Some(*f),
+            _ => None,
+        }
+    }
 }
 
 impl From<usize> for ResultItem {
     fn from(value: usize) -> Self {
-        Self::Integer(value as isize)
+        Self::from(value as u128)
     }
 }
 
@@ -106,6 +422,26 @@ impl From<isize> for ResultItem {
     }
 }
 
+/// Uses the narrowest integer variant the value fits into.
+impl From<i128> for ResultItem {
+    fn from(value: i128) -> Self {
+        match isize::try_from(value) {
+            Ok(value) => Self::Integer(value),
+            Err(_) => Self::Integer128(value),
+        }
+    }
+}
+
+/// Uses the narrowest integer variant the value fits into.
+impl From<u128> for ResultItem {
+    fn from(value: u128) -> Self {
+        match i128::try_from(value) {
+            Ok(value) => Self::from(value),
+            Err(_) => Self::UInteger128(value),
+        }
+    }
+}
+
 impl From<f64> for ResultItem {
     fn from(value: f64) -> Self {
         Self::Float(value)
@@ -135,3 +471,108 @@ impl From<()> for ResultItem {
         Self::Empty
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::ResultItem;
+
+    #[test]
+    fn accessors() {
+        assert_eq!(ResultItem::Integer(-5).as_integer(), Some(-5));
+        assert_eq!(ResultItem::Float(1.5).as_float(), Some(1.5));
+        assert_eq!(ResultItem::Boolean(true).as_bool(), Some(true));
+        assert_eq!(ResultItem::Character('x').as_char(), Some('x'));
+        assert_eq!(ResultItem::from("text").as_str(), Some("text"));
+
+        assert_eq!(ResultItem::Integer(-5).as_number(), Some(-5.0));
+        assert_eq!(ResultItem::Float(1.5).as_number(), Some(1.5));
+        assert_eq!(
+            ResultItem::UInteger128(u128::MAX).as_number(),
+            Some(u128::MAX as f64)
+        );
+
+        assert_eq!(ResultItem::Float(1.0).as_integer(), None);
+        assert_eq!(ResultItem::Integer(1).as_float(), None);
+        assert_eq!(ResultItem::from("true").as_bool(), None);
+        assert_eq!(ResultItem::from("x").as_char(), None);
+        assert_eq!(ResultItem::Character('x').as_str(), None);
+        assert_eq!(ResultItem::from("1.5").as_number(), None);
+        assert_eq!(ResultItem::Empty.as_number(), None);
+    }
+
+    #[test]
+    fn ordering() {
+        use std::collections::HashMap;
+
+        use crate::NamedItem;
+
+        let mut items = vec![
+            ResultItem::from("b"),
+            ResultItem::Float(f64::NAN),
+            ResultItem::Integer(5),
+            ResultItem::Named(Box::new(NamedItem::new("a", 1.5))),
+            ResultItem::Boolean(true),
+            ResultItem::UInteger128(u128::MAX),
+            ResultItem::Float(-1.5),
+            ResultItem::Character('x'),
+            ResultItem::Empty,
+            ResultItem::Integer128(i128::MIN),
+            ResultItem::from("a"),
+            ResultItem::Boolean(false),
+            ResultItem::Float(0.0),
+            ResultItem::Integer(-3),
+            ResultItem::Float(-0.0),
+        ];
+        items.sort();
+
+        assert_eq!(
+            items,
+            vec![
+                ResultItem::Empty,
+                ResultItem::Boolean(false),
+                ResultItem::Boolean(true),
+                ResultItem::Integer128(i128::MIN),
+                ResultItem::Integer(-3),
+                ResultItem::Integer(5),
+                ResultItem::UInteger128(u128::MAX),
+                ResultItem::Float(-1.5),
+                ResultItem::Float(-0.0),
+                ResultItem::Float(0.0),
+                ResultItem::Float(f64::NAN),
+                ResultItem::Character('x'),
+                ResultItem::from("a"),
+                ResultItem::from("b"),
+                ResultItem::Named(Box::new(NamedItem::new("a", 1.5))),
+            ]
+        );
+
+        assert_eq!(ResultItem::Integer(5), ResultItem::Integer128(5));
+        assert_ne!(ResultItem::Integer(5), ResultItem::Float(5.0));
+
+        let mut counts = HashMap::new();
+        for item in [
+            ResultItem::Integer(1),
+            ResultItem::Integer128(1),
+            ResultItem::Float(f64::NAN),
+            ResultItem::Float(f64::NAN),
+            ResultItem::from("a"),
+        ] {
+            *counts.entry(item).or_insert(0) += 1;
+        }
+        assert_eq!(counts[&ResultItem::Integer(1)], 2);
+        assert_eq!(counts[&ResultItem::Float(f64::NAN)], 2);
+        assert_eq!(counts[&ResultItem::from("a")], 1);
+    }
+
+    #[test]
+    fn csv_field() {
+        assert_eq!(ResultItem::from("plain").to_csv_field(), "plain");
+        assert_eq!(ResultItem::from("a, b").to_csv_field(), r#""a, b""#);
+        assert_eq!(
+            ResultItem::from(r#"say "hi""#).to_csv_field(),
+            r#""say ""hi""""#
+        );
+        assert_eq!(ResultItem::Float(1.5).to_csv_field(), "1.5");
+        assert_eq!(ResultItem::Empty.to_csv_field(), "");
+    }
+}
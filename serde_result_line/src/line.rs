@@ -0,0 +1,302 @@
+//! An in-memory representation of a result line
+
+use std::fmt::Display;
+
+use crate::{NamedItem, ResultItem, SerializeOptions};
+
+/// The items of a single result line, in the order they were added or parsed in.
+/// This can be built programmatically or parsed using [`from_string`](crate::from_string),
+/// and written as a result line using its [`Display`] implementation.
+///
+/// # Examples
+///
+/// ```
+/// use serde_result_line::{ResultItem, ResultLine};
+///
+/// let line = ResultLine::new()
+///     .with("algorithm", "quick sort")
+///     .with("time", 1.5)
+///     .with("n", 1000usize);
+///
+/// assert_eq!(line.to_string(), r#"RESULT algorithm="quick sort" time=1.5 n=1000"#);
+///
+/// let s = line.to_string();
+/// let parsed: ResultLine = serde_result_line::from_string(&s).unwrap();
+/// assert_eq!(parsed, line);
+/// assert_eq!(parsed.get("time"), Some(&ResultItem::Float(1.5)));
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct ResultLine {
+    items: Vec<NamedItem>,
+}
+
+/// An error occurring when inspecting or modifying the items of a [`ResultLine`].
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum LineError {
+    #[error("no item named \"{0}\"")]
+    MissingKey(String),
+    #[error("item \"{0}\" is not numeric: {1}")]
+    NotNumeric(String, ResultItem),
+    #[error("item \"{0}\" is negative: {1}")]
+    Negative(String, ResultItem),
+}
+
+impl ResultLine {
+    /// Creates an empty result line.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an item to the end of this line and returns the line, for building lines in a single expression.
+    pub fn with(mut self, name: impl Into<ResultItem>, value: impl Into<ResultItem>) -> Self {
+        self.push(name, value);
+        self
+    }
+
+    /// Adds an item to the end of this line.
+    pub fn push(&mut self, name: impl Into<ResultItem>, value: impl Into<ResultItem>) {
+        self.items.push(NamedItem::new(name, value));
+    }
+
+    /// Returns the value of the first item with the given name.
+    pub fn get(&self, key: &str) -> Option<&ResultItem> {
+        self.items
+            .iter()
+            .find(|item| item.has_name(key))
+            .map(|item| &item.value)
+    }
+
+    /// Returns an iterator over the items of this line, in order.
+    pub fn iter(&self) -> std::slice::Iter<'_, NamedItem> {
+        self.items.iter()
+    }
+
+    /// Checks that the numeric items with the given names are all non-negative.
+    ///
+    /// # Arguments
+    ///
+    /// * `keys`: The names of the items to check.
+    ///
+    /// Returns: An error for the first item which is missing, not numeric or negative.
+    pub fn validate_non_negative(&self, keys: &[&str]) -> Result<(), LineError> {
+        for &key in keys {
+            let value = self
+                .get(key)
+                .ok_or_else(|| LineError::MissingKey(key.to_owned()))?;
+            let negative = match *value {
+                ResultItem::Integer(i) => i < 0,
+                ResultItem::Integer128(i) => i < 0,
+                ResultItem::UInteger128(_) => false,
+                ResultItem::Float(f) => f < 0.0,
+                _ => return Err(LineError::NotNumeric(key.to_owned(), value.clone())),
+            };
+            if negative {
+                return Err(LineError::Negative(key.to_owned(), value.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Multiplies the numeric item with the given name by a factor, e.g. to convert between units.
+    /// The item becomes a [`Float`](ResultItem::Float), even if it was an integer before.
+    ///
+    /// # Arguments
+    ///
+    /// * `key`: The name of the item to scale.
+    /// * `factor`: The factor to multiply the item's value with.
+    ///
+    /// Returns: An error if the item is missing or not numeric, in which case the line is left unchanged.
+    pub fn scale(&mut self, key: &str, factor: f64) -> Result<(), LineError> {
+        let value = self
+            .get_mut(key)
+            .ok_or_else(|| LineError::MissingKey(key.to_owned()))?;
+        let number = value
+            .as_number()
+            .ok_or_else(|| LineError::NotNumeric(key.to_owned(), value.clone()))?;
+        *value = ResultItem::Float(number * factor);
+        Ok(())
+    }
+
+    /// Renders the values of this line as a CSV row, in the order of the items.
+    /// The names of the items are not part of the row.
+    pub fn to_csv_row(&self) -> String {
+        self.items
+            .iter()
+            .map(|item| item.value.to_csv_field())
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// Finds the value of the first item with the given name for modification.
+    fn get_mut(&mut self, key: &str) -> Option<&mut ResultItem> {
+        self.items
+            .iter_mut()
+            .find(|item| item.has_name(key))
+            .map(|item| &mut item.value)
+    }
+}
+
+/// Writes the line including the `RESULT` tag, e.g. `RESULT a=1 b=text`.
+/// Names which cannot be represented in a result line, like names containing quotes, are written as they are.
+/// Use [`to_string`](crate::to_string) to detect them instead.
+impl Display for ResultLine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let options = SerializeOptions::default();
+        write!(f, "RESULT")?;
+        for item in &self.items {
+            write!(f, " ")?;
+            item.write(f, &options)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> IntoIterator for &'a ResultLine {
+    type Item = &'a NamedItem;
+
+    type IntoIter = std::slice::Iter<'a, NamedItem>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a> FromIterator<(&'a str, ResultItem)> for ResultLine {
+    fn from_iter<T: IntoIterator<Item = (&'a str, ResultItem)>>(iter: T) -> Self {
+        Self {
+            items: iter
+                .into_iter()
+                .map(|(name, value)| NamedItem::new(name, value))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::LineError;
+    use crate::{ResultItem, ResultLine};
+
+    #[test]
+    fn csv_row() {
+        let line: ResultLine = [
+            ("a", ResultItem::from("x, y")),
+            ("b", ResultItem::from(r#"say "hi""#)),
+            ("c", ResultItem::Integer(12)),
+            ("d", ResultItem::Empty),
+            ("e", ResultItem::Float(1.5)),
+            ("f", ResultItem::Boolean(true)),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(line.to_csv_row(), r#""x, y","say ""hi""",12,,1.5,true"#);
+
+        let line: ResultLine = crate::from_string(r#"RESULT a="x, y" b=12 c=text"#).unwrap();
+        assert_eq!(line.to_csv_row(), r#""x, y",12,text"#);
+    }
+
+    #[test]
+    fn non_negative() {
+        let line: ResultLine =
+            crate::from_string("RESULT count=12 time=0.5 zero=0 name=test").unwrap();
+        assert_eq!(
+            line.validate_non_negative(&["count", "time", "zero"]),
+            Ok(())
+        );
+
+        let line: ResultLine = crate::from_string("RESULT count=12 time=-0.5 name=test").unwrap();
+        assert_eq!(
+            line.validate_non_negative(&["count", "time"]),
+            Err(LineError::Negative(
+                "time".to_owned(),
+                ResultItem::Float(-0.5)
+            ))
+        );
+        assert_eq!(
+            line.validate_non_negative(&["name"]),
+            Err(LineError::NotNumeric(
+                "name".to_owned(),
+                ResultItem::from("test")
+            ))
+        );
+        assert_eq!(
+            line.validate_non_negative(&["memory"]),
+            Err(LineError::MissingKey("memory".to_owned()))
+        );
+    }
+
+    #[test]
+    fn scale() {
+        let mut line: ResultLine =
+            crate::from_string("RESULT time_ns=1500000 other=2.5 name=test").unwrap();
+
+        assert_eq!(line.scale("time_ns", 1e-6), Ok(()));
+        assert_eq!(line.get("time_ns"), Some(&ResultItem::Float(1.5)));
+        assert_eq!(line.get("other"), Some(&ResultItem::Float(2.5)));
+
+        assert_eq!(
+            line.scale("name", 1e-6),
+            Err(LineError::NotNumeric(
+                "name".to_owned(),
+                ResultItem::from("test")
+            ))
+        );
+        assert_eq!(
+            line.scale("memory", 1e-6),
+            Err(LineError::MissingKey("memory".to_owned()))
+        );
+    }
+
+    #[test]
+    fn build_and_round_trip() {
+        let mut line = ResultLine::new()
+            .with("name", "merge sort")
+            .with("n", 1000usize)
+            .with("time", 0.5)
+            .with("stable", true);
+        line.push("flag", "true");
+        line.push("empty", "");
+
+        assert_eq!(
+            line.to_string(),
+            r#"RESULT name="merge sort" n=1000 time=0.5 stable=true flag="true" empty="""#
+        );
+        assert_eq!(line.get("n"), Some(&ResultItem::Integer(1000)));
+        assert_eq!(line.get("missing"), None);
+
+        let names: Vec<String> = line.iter().map(|item| item.name().to_string()).collect();
+        assert_eq!(names, ["name", "n", "time", "stable", "flag", "empty"]);
+
+        let s = line.to_string();
+        let parsed: ResultLine = crate::from_string(&s).unwrap();
+        assert_eq!(parsed, line);
+    }
+
+    #[test]
+    fn sequences_without_separator() {
+        let options = crate::ParseOptions::new().sequence_separator(',');
+        let line: ResultLine =
+            crate::from_string_with_options("RESULT a=1,2 b=x", &options).unwrap();
+
+        // Without a sequence separator, the elements are written as separate items which read back as they are
+        let s = line.to_string();
+        assert_eq!(s, "RESULT a_0=1 a_1=2 b=x");
+        let parsed: ResultLine = crate::from_string(&s).unwrap();
+        assert_eq!(parsed.get("a_1"), Some(&ResultItem::Integer(2)));
+    }
+
+    #[test]
+    fn whole_floats_round_trip() {
+        let mut line = ResultLine::new()
+            .with("t", 2.0)
+            .with("u", 1e20)
+            .with("n", 4usize);
+        line.scale("n", 0.5).unwrap();
+
+        let s = line.to_string();
+        assert_eq!(s, "RESULT t=2.0 u=100000000000000000000.0 n=2.0");
+        let parsed: ResultLine = crate::from_string(&s).unwrap();
+        assert_eq!(parsed, line);
+    }
+}
@@ -1,19 +1,25 @@
 //! Serialization of structs into result lines using serde
 
+use std::collections::BTreeMap;
 use std::fmt::Display;
 
 use serde::{
-    ser::{SerializeMap, SerializeStruct, SerializeStructVariant},
+    ser::{
+        SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant, SerializeTuple,
+        SerializeTupleStruct, SerializeTupleVariant,
+    },
     Serializer,
 };
 
-use crate::{NamedItem, ResultItem};
+use crate::{NamedItem, ParseOptions, ResultItem, SCHEMA_VERSION_KEY};
 
 ///
 /// Takes a serializable struct and turns it into a result line.
-/// It is important to say that serializing a struct into a result line only works on completely flat structs!
-/// That means that nested structs are not supported, unless #[serde(flatten)] is used.
-/// This method works on [HashMap]s and [BTreeMap]s as well however.
+/// Fields of nested structs and maps are named after the field containing them, joined with a `.`,
+/// e.g. `build.time=1.5 query.time=0.2`. To use the inner names as they are, use `#[serde(flatten)]`.
+/// This method works on [HashMap]s and [BTreeMap]s as well.
+/// Sequences, like [Vec]s or tuples, are supported as field values. Each of their elements becomes a separate item,
+/// named after the field and the element's index, e.g. `thread_0=1.5 thread_1=1.7`.
 ///
 /// # Arguments
 ///
@@ -26,8 +32,8 @@ use crate::{NamedItem, ResultItem};
 /// ```
 /// use std::collections::BTreeMap;
 ///
-/// // Any of these variants work. Empty variants are ignored in output
-/// // That means that [Option::None] is ignored as well.
+/// // Any of these variants work. Unit variants are written as their name,
+/// // while empty values like [Option::None] are ignored in output.
 /// #[derive(serde::Serialize)]
 /// enum E {
 ///     A,
@@ -58,24 +64,480 @@ use crate::{NamedItem, ResultItem};
 /// assert_eq!(serde_result_line::to_string(&t), Ok(r#"RESULT a="hello world" b=-123423904 "map key"=100 d=true e=12"#.to_string()));
 /// ```
 pub fn to_string<T: serde::Serialize>(t: &T) -> Result<String, Erra> {
+    to_string_with_options(t, &SerializeOptions::default())
+}
+
+/// Takes a serializable struct and turns it into a result line, like [`to_string`], using the given options.
+///
+/// # Arguments
+///
+/// * `t`: The struct to serialize
+/// * `options`: The options controlling how the result line is built
+///
+/// Returns: The struct serialized into a result line.
+///
+/// # Examples
+///
+/// ```
+/// use serde_result_line::SerializeOptions;
+///
+/// #[derive(serde::Serialize)]
+/// struct Test {
+///     b: i64,
+///     a: &'static str,
+/// }
+///
+/// let options = SerializeOptions::new().column_template(&["a", "b", "c"]);
+/// let t = Test { b: 12, a: "text" };
+///
+/// assert_eq!(
+///     serde_result_line::to_string_with_options(&t, &options),
+///     Ok("RESULT a=text b=12 c=".to_string())
+/// );
+/// ```
+pub fn to_string_with_options<T: serde::Serialize>(
+    t: &T,
+    options: &SerializeOptions,
+) -> Result<String, Erra> {
     let mut ser = ResultLineStructurizer {
         current_name: None,
+        prefixes: vec![],
         output: vec![],
+        sequence_separator: options.sequence_separator,
+        in_sequence: false,
     };
     t.serialize(&mut ser)?;
 
+    let mut items = ser.output;
+    if options.sort_keys {
+        items.sort_by_cached_key(|item| item.name.to_string());
+    }
+    if let Some(template) = &options.column_template {
+        items = apply_template(items, template, options.extra_columns)?;
+    }
+    if let Some(version) = options.schema_version {
+        if items.iter().any(|item| item.has_name(SCHEMA_VERSION_KEY)) {
+            return Err(Erra::InvalidKey(SCHEMA_VERSION_KEY.to_owned()));
+        }
+        items.insert(0, NamedItem::new(SCHEMA_VERSION_KEY, version as usize));
+    }
+
+    join_items(items, options)
+}
+
+/// Takes a serializable struct and turns it into a result line, like [`to_string`],
+/// but with the items sorted by their keys.
+/// This makes the output deterministic even for maps without a fixed iteration order, like [HashMap]s.
+///
+/// # Arguments
+///
+/// * `t`: The struct to serialize
+///
+/// Returns: The struct serialized into a result line with sorted items.
+///
+/// # Examples
+///
+/// ```
+/// #[derive(serde::Serialize)]
+/// struct Test {
+///     b: i64,
+///     a: &'static str,
+/// }
+///
+/// assert_eq!(
+///     serde_result_line::to_string_sorted(&Test { b: 12, a: "text" }),
+///     Ok("RESULT a=text b=12".to_string())
+/// );
+/// ```
+pub fn to_string_sorted<T: serde::Serialize>(t: &T) -> Result<String, Erra> {
+    to_string_with_options(t, &SerializeOptions::new().sort_keys(true))
+}
+
+/// Turns a map of primitive values into a result line, without going through serde.
+/// The output is the same as that of [`to_string`] for the same map, but this is faster for large maps.
+/// Empty values are ignored in the output.
+///
+/// # Arguments
+///
+/// * `map`: The map to turn into a result line
+///
+/// Returns: The map's entries as a result line.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::BTreeMap;
+///
+/// let mut map = BTreeMap::new();
+/// map.insert("time".to_owned(), 1.5);
+/// map.insert("memory used".to_owned(), 12.0);
+///
+/// assert_eq!(serde_result_line::to_string_map(&map), serde_result_line::to_string(&map));
+/// ```
+pub fn to_string_map<V>(map: &BTreeMap<String, V>) -> Result<String, Erra>
+where
+    V: Into<ResultItem> + Copy,
+{
+    let options = SerializeOptions::default();
+    // Reserve room for the keys and a short value per item up front
+    let capacity = map.keys().map(|name| name.len() + 8).sum::<usize>();
+    let mut s = String::with_capacity("RESULT".len() + capacity);
+    s.push_str("RESULT");
+    for (name, &value) in map {
+        let value = value.into();
+        if value.is_empty() {
+            continue;
+        }
+        validate_key_str(name)?;
+        validate_value(name, &value)?;
+        s.push(' ');
+        crate::write_key(&mut s, name, &options).expect("writing to a string cannot fail");
+        crate::write_value(&mut s, &value, &options).expect("writing to a string cannot fail");
+    }
+    Ok(s)
+}
+
+/// Turns a list of named measurements into a result line, without going through serde.
+/// The items are written in the given order, with keys quoted where necessary.
+/// Values are always written with a decimal point, so that they are read back as floats, e.g. `12.0`.
+///
+/// # Arguments
+///
+/// * `measurements`: The names and values of the measurements
+///
+/// Returns: The measurements as a result line,
+/// or an [`Erra::InvalidKey`] error if a name is empty or contains quotes.
+///
+/// # Examples
+///
+/// ```
+/// let measurements = vec![("time".to_owned(), 1.5), ("memory used".to_owned(), 12.0)];
+///
+/// assert_eq!(
+///     serde_result_line::to_string_measurements(&measurements),
+///     Ok(r#"RESULT time=1.5 "memory used"=12.0"#.to_string())
+/// );
+/// ```
+pub fn to_string_measurements(measurements: &[(impl AsRef<str>, f64)]) -> Result<String, Erra> {
+    let options = SerializeOptions::default();
     let mut s = "RESULT".to_owned();
-    for item in ser.output {
+    for (name, value) in measurements {
+        let name = name.as_ref();
+        validate_key_str(name)?;
         s.push(' ');
-        s.push_str(&item.to_string())
+        crate::write_key(&mut s, name, &options).expect("writing to a string cannot fail");
+        crate::write_value(&mut s, &ResultItem::Float(*value), &options)
+            .expect("writing to a string cannot fail");
+    }
+    Ok(s)
+}
+
+/// How items which are not part of a column template are handled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExtraColumns {
+    /// Extra items are left out of the output.
+    #[default]
+    Drop,
+    /// Extra items result in an [`Erra::UnexpectedColumn`] error.
+    Error,
+}
+
+/// How float values are written.
+/// All formats write finite values with a decimal point or an exponent,
+/// so that the values are read back as floats, e.g. `5.0` instead of `5`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FloatFormat {
+    /// The shortest representation which reads back as the same value, e.g. `0.30000000000000004` or `5.0`.
+    #[default]
+    Shortest,
+    /// A fixed number of decimal places, e.g. `0.300` for 3 decimal places.
+    Decimals(usize),
+    /// A limited number of significant figures, e.g. `1230.0` for 3 significant figures of `1234.5`.
+    SignificantFigures(usize),
+}
+
+/// Options controlling how result lines are serialized.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SerializeOptions {
+    column_template: Option<Vec<String>>,
+    extra_columns: ExtraColumns,
+    float_format: FloatFormat,
+    decimal_separator: char,
+    pub(crate) key_value_separator: String,
+    schema_version: Option<u32>,
+    pub(crate) sequence_separator: Option<char>,
+    sort_keys: bool,
+}
+
+impl Default for SerializeOptions {
+    fn default() -> Self {
+        Self {
+            column_template: None,
+            extra_columns: ExtraColumns::default(),
+            float_format: FloatFormat::default(),
+            decimal_separator: '.',
+            key_value_separator: "=".to_owned(),
+            schema_version: None,
+            sequence_separator: None,
+            sort_keys: false,
+        }
+    }
+}
+
+impl SerializeOptions {
+    /// Creates the default options, which output all items in the order they were serialized in.
+    pub fn new() -> Self {
+        Self::default()
     }
 
+    /// Sets a fixed set of columns every result line consists of, in the given order.
+    /// Columns which are missing from the serialized value are output as empty items, e.g. `c=`.
+    /// Items which are not part of the template are handled according to [`SerializeOptions::extra_columns`].
+    pub fn column_template(mut self, columns: &[&str]) -> Self {
+        self.column_template = Some(columns.iter().map(|&c| c.to_owned()).collect());
+        self
+    }
+
+    /// Sets how items which are not part of the column template are handled. Extra items are dropped by default.
+    pub fn extra_columns(mut self, extra_columns: ExtraColumns) -> Self {
+        self.extra_columns = extra_columns;
+        self
+    }
+
+    /// Sets how float values are written. Integers, booleans and text are not affected.
+    pub fn float_format(mut self, float_format: FloatFormat) -> Self {
+        self.float_format = float_format;
+        self
+    }
+
+    /// Sets the character separating the integer and fractional part of floats, e.g. `,` to write `2,75`.
+    /// Lines written this way can be read using [`ParseOptions::decimal_separator`] with the same separator.
+    /// Defaults to `.`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the separator conflicts with the rest of the format,
+    /// i.e. if it is whitespace, a quote, a digit, a sign, an exponent marker or part of the key-value separator.
+    pub fn decimal_separator(mut self, separator: char) -> Self {
+        crate::check_separators(
+            separator,
+            &self.key_value_separator,
+            self.sequence_separator,
+        );
+        self.decimal_separator = separator;
+        self
+    }
+
+    /// Sets the separator between the key and the value of an item, e.g. `=>` to write `a=>1`.
+    /// Lines written this way can be read using [`ParseOptions::key_value_separator`] with the same separator.
+    /// Defaults to `=`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the separator is empty, contains whitespace or quotes, or contains the decimal separator.
+    pub fn key_value_separator(mut self, separator: impl Into<String>) -> Self {
+        let separator = separator.into();
+        crate::check_separators(self.decimal_separator, &separator, self.sequence_separator);
+        self.key_value_separator = separator;
+        self
+    }
+
+    /// Sets the character separating the elements of sequences, which are then written as a single item, e.g. `a=1,2,3`.
+    /// Lines written this way can be read using [`ParseOptions::sequence_separator`] with the same separator.
+    /// No trailing separator is written, so sequences ending in an empty element result in an [`Erra::InvalidSequence`] error.
+    /// For the same reason, a sequence with a single element is written like a single value, e.g. `a=5`,
+    /// and is read back as that value instead of a sequence.
+    /// By default, each element is written as a separate item named after the sequence and its index, e.g. `a_0=1`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the separator conflicts with the rest of the format,
+    /// i.e. if it is whitespace, a quote, a digit, a sign, the decimal separator or part of the key-value separator.
+    pub fn sequence_separator(mut self, separator: char) -> Self {
+        crate::check_separators(
+            self.decimal_separator,
+            &self.key_value_separator,
+            Some(separator),
+        );
+        self.sequence_separator = Some(separator);
+        self
+    }
+
+    /// Sets whether items are sorted by their keys, as they appear in the output.
+    /// The sort is stable, so items with the same key keep their relative order.
+    /// A column template takes precedence over the sorted order. Disabled by default.
+    pub fn sort_keys(mut self, sort: bool) -> Self {
+        self.sort_keys = sort;
+        self
+    }
+
+    /// Sets a schema version which is written as the first item of every result line, e.g. `__schema=2`.
+    /// It can be read back using [`from_string_versioned`](crate::from_string_versioned).
+    /// Serializing a value which has an item named like the schema version results in an [`Erra::InvalidKey`] error.
+    pub fn schema_version(mut self, version: u32) -> Self {
+        self.schema_version = Some(version);
+        self
+    }
+
+    /// Creates options for parsing lines written with these options.
+    pub(crate) fn parse_options(&self) -> ParseOptions {
+        let options = ParseOptions::new()
+            .key_value_separator(self.key_value_separator.as_str())
+            .decimal_separator(self.decimal_separator);
+        match self.sequence_separator {
+            Some(separator) => options.sequence_separator(separator),
+            None => options,
+        }
+    }
+
+    /// Checks whether floats are written as their [`Display`] representation, which needs no intermediate string.
+    pub(crate) fn writes_floats_as_display(&self) -> bool {
+        self.float_format == FloatFormat::Shortest && self.decimal_separator == '.'
+    }
+
+    /// Formats a float value according to these options.
+    pub(crate) fn format_float(&self, v: f64) -> String {
+        let formatted = self.format_float_with_point(v);
+        match self.decimal_separator {
+            '.' => formatted,
+            separator => formatted.replacen('.', &separator.to_string(), 1),
+        }
+    }
+
+    /// Formats a float value according to the float format, using `.` as decimal separator.
+    fn format_float_with_point(&self, v: f64) -> String {
+        let formatted = match self.float_format {
+            _ if !v.is_finite() => return v.to_string(),
+            FloatFormat::Shortest => v.to_string(),
+            FloatFormat::Decimals(decimals) => format!("{v:.decimals$}"),
+            FloatFormat::SignificantFigures(figures) => {
+                // Rounding through the exponent notation avoids printing the exponent itself
+                let rounded = format!("{v:.*e}", figures.saturating_sub(1));
+                rounded.parse::<f64>().unwrap_or(v).to_string()
+            }
+        };
+        with_decimal_point(formatted)
+    }
+}
+
+/// Appends `.0` to a formatted finite float without a decimal point or exponent, so that it is read back as a float.
+fn with_decimal_point(formatted: String) -> String {
+    if formatted.contains(['.', 'e']) {
+        formatted
+    } else {
+        formatted + ".0"
+    }
+}
+
+/// Reorders the items to match the template, filling missing columns with empty items.
+fn apply_template(
+    mut items: Vec<NamedItem>,
+    template: &[String],
+    extra_columns: ExtraColumns,
+) -> Result<Vec<NamedItem>, Erra> {
+    let mut ordered = Vec::with_capacity(template.len());
+    for column in template {
+        match items
+            .iter()
+            .position(|item| item.name.to_string() == *column)
+        {
+            Some(i) => ordered.push(items.remove(i)),
+            None => ordered.push(NamedItem::new(column.as_str(), ResultItem::Empty)),
+        }
+    }
+
+    match (extra_columns, items.first()) {
+        (ExtraColumns::Error, Some(extra)) => Err(Erra::UnexpectedColumn(extra.name.to_string())),
+        _ => Ok(ordered),
+    }
+}
+
+/// Joins named items into a result line, starting with the `RESULT` tag.
+/// Fails if the name or value of an item cannot be represented in a result line.
+pub(crate) fn join_items(
+    items: impl IntoIterator<Item = NamedItem>,
+    options: &SerializeOptions,
+) -> Result<String, Erra> {
+    let mut s = "RESULT".to_owned();
+    for item in items {
+        validate_key(&item.name)?;
+        validate_value(&item.name, &item.value)?;
+        validate_sequence(&item, options)?;
+        s.push(' ');
+        item.write(&mut s, options)
+            .expect("writing to a string cannot fail");
+    }
     Ok(s)
 }
 
+/// Checks that a name can be written such that parsing the result line yields the exact same name.
+/// Names containing whitespace or `=` are quoted, but empty names and names containing quotes cannot be represented.
+fn validate_key(name: &ResultItem) -> Result<(), Erra> {
+    match name {
+        ResultItem::Text(name) => validate_key_str(name),
+        name => validate_key_str(&name.to_string()),
+    }
+}
+
+/// Checks a name given as a string, like [`validate_key`].
+fn validate_key_str(name: &str) -> Result<(), Erra> {
+    if name.is_empty() || name.contains('"') {
+        return Err(Erra::InvalidKey(name.to_owned()));
+    }
+    Ok(())
+}
+
+/// Checks that a text or character value can be written such that parsing the result line yields the exact same text.
+/// Quoted values end at the next quote, so text containing quotes cannot be represented.
+fn validate_value(name: impl Display, value: &ResultItem) -> Result<(), Erra> {
+    match value {
+        ResultItem::Text(t) if t.contains('"') => Err(Erra::InvalidValue(name.to_string())),
+        ResultItem::Character('"') => Err(Erra::InvalidValue(name.to_string())),
+        _ => Ok(()),
+    }
+}
+
+/// Checks that a sequence value can be written such that parsing the result line yields the exact same elements.
+/// Elements which are joined with a separator are written without quotes,
+/// and the sequence must not end in an empty element, which would be written as a trailing separator.
+fn validate_sequence(item: &NamedItem, options: &SerializeOptions) -> Result<(), Erra> {
+    let ResultItem::Sequence(elements) = &item.value else {
+        return Ok(());
+    };
+    let parse_options = options.parse_options();
+    let valid_text = |t: &str| {
+        !t.contains('"')
+            && options.sequence_separator.is_none_or(|separator| {
+                !t.contains(separator) && crate::de::is_bare_text(t, &parse_options)
+            })
+    };
+    let valid = elements.iter().all(|element| match element {
+        ResultItem::Text(t) => valid_text(t),
+        ResultItem::Character(c) => valid_text(&c.to_string()),
+        ResultItem::Named(_) | ResultItem::Sequence(_) => false,
+        _ => true,
+    });
+    let trailing_separator =
+        options.sequence_separator.is_some() && elements.last().is_some_and(ResultItem::is_empty);
+    if !valid || trailing_separator {
+        return Err(Erra::InvalidSequence(item.name.to_string()));
+    }
+    Ok(())
+}
+
+/// The separator between the name of a nested struct and the names of its fields.
+const NESTING_SEPARATOR: char = '.';
+
 struct ResultLineStructurizer {
     current_name: Option<ResultItem>,
+    /// The names of the structs and maps currently being serialized, which prefix the names of their fields.
+    /// Top-level structs and maps have no name.
+    prefixes: Vec<Option<String>>,
     output: Vec<NamedItem>,
+    /// The separator sequences are joined with, or `None` if their elements are separate items.
+    sequence_separator: Option<char>,
+    /// Whether the elements of a joined sequence are currently being serialized.
+    in_sequence: bool,
 }
 
 impl ResultLineStructurizer {
@@ -86,6 +548,74 @@ impl ResultLineStructurizer {
             t.into()
         }
     }
+
+    /// Starts serializing a struct or map, whose fields are prefixed with the struct's name if it is nested.
+    fn begin_nested(&mut self) -> Result<(), Erra> {
+        if self.in_sequence {
+            return Err(Erra::Unsupported("nested value in a sequence"));
+        }
+        let name = self.current_name.take().map(|name| name.to_string());
+        self.prefixes.push(name);
+        Ok(())
+    }
+
+    /// Finishes serializing a struct or map.
+    /// Its fields are already part of the output, so a nested struct itself has no value left.
+    fn end_nested(&mut self) -> ResultItem {
+        match self.prefixes.pop().flatten() {
+            Some(name) => {
+                ResultItem::Named(Box::new(NamedItem::new(name.as_str(), ResultItem::Empty)))
+            }
+            None => ResultItem::Empty,
+        }
+    }
+
+    /// Prefixes the name of a field with the names of the structs containing it.
+    fn prefixed(&self, name: ResultItem) -> ResultItem {
+        match self.prefixes.last() {
+            Some(Some(prefix)) => ResultItem::Text(format!("{prefix}{NESTING_SEPARATOR}{name}")),
+            _ => name,
+        }
+    }
+
+    /// Adds the result of serializing a value to the output.
+    /// Empty values are skipped, while values without a name result in an error.
+    fn push(&mut self, result: ResultItem) -> Result<(), Erra> {
+        match result {
+            ResultItem::Named(mut item) if !item.value.is_empty() => {
+                self.output.push(std::mem::take(&mut *item))
+            }
+            ResultItem::Named(_) => {}
+            _ => return Err(Erra::UnnamedItem),
+        }
+        Ok(())
+    }
+}
+
+/// Serializes the elements of a named sequence, either as separate items named after the sequence and the element index,
+/// or joined into a single item if a sequence separator is set.
+struct NamedSequence<'a> {
+    ser: &'a mut ResultLineStructurizer,
+    name: ResultItem,
+    index: usize,
+    /// The elements of a joined sequence
+    elements: Option<Vec<ResultItem>>,
+}
+
+impl<'a> NamedSequence<'a> {
+    fn new(ser: &'a mut ResultLineStructurizer) -> Result<Self, Erra> {
+        if ser.in_sequence {
+            return Err(Erra::Unsupported("nested value in a sequence"));
+        }
+        let name = ser.current_name.take().ok_or(Erra::UnnamedSequence)?;
+        let elements = ser.sequence_separator.map(|_| vec![]);
+        Ok(Self {
+            ser,
+            name,
+            index: 0,
+            elements,
+        })
+    }
 }
 
 #[derive(Debug, thiserror::Error, PartialEq, Eq)]
@@ -96,6 +626,16 @@ pub enum Erra {
     Unsupported(&'static str),
     #[error("unnamed item found")]
     UnnamedItem,
+    #[error("sequences are only supported as the value of a named field")]
+    UnnamedSequence,
+    #[error("column \"{0}\" is not part of the column template")]
+    UnexpectedColumn(String),
+    #[error("key {0:?} cannot be represented in a result line")]
+    InvalidKey(String),
+    #[error("value of {0:?} cannot be represented in a result line")]
+    InvalidValue(String),
+    #[error("sequence {0:?} cannot be represented in a result line")]
+    InvalidSequence(String),
 }
 
 impl serde::ser::Error for Erra {
@@ -112,13 +652,13 @@ impl<'a> Serializer for &'a mut ResultLineStructurizer {
 
     type Error = Erra;
 
-    type SerializeSeq = serde::ser::Impossible<Self::Ok, Self::Error>;
+    type SerializeSeq = NamedSequence<'a>;
 
-    type SerializeTuple = serde::ser::Impossible<Self::Ok, Self::Error>;
+    type SerializeTuple = NamedSequence<'a>;
 
-    type SerializeTupleStruct = serde::ser::Impossible<Self::Ok, Self::Error>;
+    type SerializeTupleStruct = NamedSequence<'a>;
 
-    type SerializeTupleVariant = serde::ser::Impossible<Self::Ok, Self::Error>;
+    type SerializeTupleVariant = NamedSequence<'a>;
 
     type SerializeMap = Self;
 
@@ -143,7 +683,11 @@ impl<'a> Serializer for &'a mut ResultLineStructurizer {
     }
 
     fn serialize_i64(self, v: i64) -> Result<Self::Ok, Self::Error> {
-        Ok(self.eat(v as isize))
+        Ok(self.eat(v as i128))
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Self::Ok, Self::Error> {
+        Ok(self.eat(v))
     }
 
     fn serialize_u8(self, v: u8) -> Result<Self::Ok, Self::Error> {
@@ -159,7 +703,11 @@ impl<'a> Serializer for &'a mut ResultLineStructurizer {
     }
 
     fn serialize_u64(self, v: u64) -> Result<Self::Ok, Self::Error> {
-        Ok(self.eat(v as usize))
+        Ok(self.eat(v as u128))
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Self::Ok, Self::Error> {
+        Ok(self.eat(v))
     }
 
     fn serialize_f32(self, v: f32) -> Result<Self::Ok, Self::Error> {
@@ -186,9 +734,9 @@ impl<'a> Serializer for &'a mut ResultLineStructurizer {
         self.serialize_unit()
     }
 
-    fn serialize_some<T: ?Sized>(self, value: &T) -> Result<Self::Ok, Self::Error>
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok, Self::Error>
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
         value.serialize(self)
     }
@@ -205,23 +753,23 @@ impl<'a> Serializer for &'a mut ResultLineStructurizer {
         self,
         _name: &'static str,
         _variant_index: u32,
-        _variant: &'static str,
+        variant: &'static str,
     ) -> Result<Self::Ok, Self::Error> {
-        self.serialize_unit()
+        self.serialize_str(variant)
     }
 
-    fn serialize_newtype_struct<T: ?Sized>(
+    fn serialize_newtype_struct<T>(
         self,
         _name: &'static str,
         value: &T,
     ) -> Result<Self::Ok, Self::Error>
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
         value.serialize(self)
     }
 
-    fn serialize_newtype_variant<T: ?Sized>(
+    fn serialize_newtype_variant<T>(
         self,
         _name: &'static str,
         _variant_index: u32,
@@ -229,17 +777,17 @@ impl<'a> Serializer for &'a mut ResultLineStructurizer {
         value: &T,
     ) -> Result<Self::Ok, Self::Error>
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
         value.serialize(self)
     }
 
     fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
-        Err(Self::Error::Unsupported("seq"))
+        NamedSequence::new(self)
     }
 
     fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Self::Error> {
-        Err(Self::Error::Unsupported("tuple"))
+        NamedSequence::new(self)
     }
 
     fn serialize_tuple_struct(
@@ -247,7 +795,7 @@ impl<'a> Serializer for &'a mut ResultLineStructurizer {
         _name: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeTupleStruct, Self::Error> {
-        Err(Self::Error::Unsupported("tuple struct"))
+        NamedSequence::new(self)
     }
 
     fn serialize_tuple_variant(
@@ -257,10 +805,11 @@ impl<'a> Serializer for &'a mut ResultLineStructurizer {
         _variant: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeTupleVariant, Self::Error> {
-        Err(Self::Error::Unsupported("tuple variant"))
+        NamedSequence::new(self)
     }
 
     fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        self.begin_nested()?;
         Ok(self)
     }
 
@@ -269,6 +818,7 @@ impl<'a> Serializer for &'a mut ResultLineStructurizer {
         _name: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeStruct, Self::Error> {
+        self.begin_nested()?;
         Ok(self)
     }
 
@@ -283,73 +833,58 @@ impl<'a> Serializer for &'a mut ResultLineStructurizer {
     }
 }
 
-impl<'a> SerializeMap for &'a mut ResultLineStructurizer {
+impl SerializeMap for &mut ResultLineStructurizer {
     type Ok = <Self as Serializer>::Ok;
 
     type Error = <Self as Serializer>::Error;
 
-    fn serialize_key<T: ?Sized>(&mut self, key: &T) -> Result<(), Self::Error>
+    fn serialize_key<T>(&mut self, key: &T) -> Result<(), Self::Error>
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
         let res_item = key.serialize(&mut **self)?;
-        self.current_name = Some(res_item);
+        self.current_name = Some(self.prefixed(res_item));
         Ok(())
     }
 
-    fn serialize_value<T: ?Sized>(&mut self, value: &T) -> Result<(), Self::Error>
+    fn serialize_value<T>(&mut self, value: &T) -> Result<(), Self::Error>
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
         let result = value.serialize(&mut **self)?;
-        match result {
-            ResultItem::Named(mut item) if !item.value.is_empty() => {
-                self.output.push(std::mem::take(&mut *item))
-            }
-            ResultItem::Named(_) => {}
-            _ => return Err(Self::Error::UnnamedItem),
-        }
-        Ok(())
+        self.push(result)
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        Ok(ResultItem::Empty)
+        Ok(self.end_nested())
     }
 }
 
-impl<'a> SerializeStruct for &'a mut ResultLineStructurizer {
+impl SerializeStruct for &mut ResultLineStructurizer {
     type Ok = <Self as Serializer>::Ok;
 
     type Error = <Self as Serializer>::Error;
 
-    fn serialize_field<T: ?Sized>(
-        &mut self,
-        key: &'static str,
-        value: &T,
-    ) -> Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error>
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
         <Self as SerializeMap>::serialize_entry(self, key, value)
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        Ok(ResultItem::Empty)
+        Ok(self.end_nested())
     }
 }
 
-impl<'a> SerializeStructVariant for &'a mut ResultLineStructurizer {
+impl SerializeStructVariant for &mut ResultLineStructurizer {
     type Ok = <Self as Serializer>::Ok;
 
     type Error = <Self as Serializer>::Error;
 
-    fn serialize_field<T: ?Sized>(
-        &mut self,
-        key: &'static str,
-        value: &T,
-    ) -> Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error>
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
         <Self as SerializeStruct>::serialize_field(self, key, value)
     }
@@ -359,6 +894,91 @@ impl<'a> SerializeStructVariant for &'a mut ResultLineStructurizer {
     }
 }
 
+impl SerializeSeq for NamedSequence<'_> {
+    type Ok = ResultItem;
+
+    type Error = Erra;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + serde::Serialize,
+    {
+        if let Some(elements) = &mut self.elements {
+            self.ser.in_sequence = true;
+            let result = value.serialize(&mut *self.ser);
+            self.ser.in_sequence = false;
+            elements.push(result?);
+            return Ok(());
+        }
+        self.ser.current_name = Some(ResultItem::Text(format!("{}_{}", self.name, self.index)));
+        self.index += 1;
+        let result = value.serialize(&mut *self.ser)?;
+        self.ser.push(result)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        let value = match self.elements {
+            Some(elements) if !elements.is_empty() => ResultItem::Sequence(elements),
+            // The elements are already part of the output, so the sequence itself has no value left
+            _ => ResultItem::Empty,
+        };
+        Ok(ResultItem::Named(Box::new(NamedItem::new(
+            self.name, value,
+        ))))
+    }
+}
+
+impl SerializeTuple for NamedSequence<'_> {
+    type Ok = ResultItem;
+
+    type Error = Erra;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + serde::Serialize,
+    {
+        <Self as SerializeSeq>::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        <Self as SerializeSeq>::end(self)
+    }
+}
+
+impl SerializeTupleStruct for NamedSequence<'_> {
+    type Ok = ResultItem;
+
+    type Error = Erra;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + serde::Serialize,
+    {
+        <Self as SerializeSeq>::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        <Self as SerializeSeq>::end(self)
+    }
+}
+
+impl SerializeTupleVariant for NamedSequence<'_> {
+    type Ok = ResultItem;
+
+    type Error = Erra;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + serde::Serialize,
+    {
+        <Self as SerializeSeq>::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        <Self as SerializeSeq>::end(self)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use std::collections::BTreeMap;
@@ -403,6 +1023,691 @@ mod test {
 
         println!("{}", super::to_string(&t).unwrap());
 
-        assert_eq!(super::to_string(&t), Ok(r#"RESULT a="hello world" b=-123423904 "a key"=8123 "another key"=1850 nowhitespace=8123 "yet another key"=21850 d=true e="this is an owned string with unicode" g="string in a variant" h=12356"#.to_string()))
+        assert_eq!(super::to_string(&t), Ok(r#"RESULT a="hello world" b=-123423904 "a key"=8123 "another key"=1850 nowhitespace=8123 "yet another key"=21850 d=true e="this is an owned string with unicode" f=A g="string in a variant" h=12356"#.to_string()))
+    }
+
+    #[test]
+    fn wide_integers() {
+        use std::collections::HashMap;
+
+        use crate::ResultItem;
+
+        #[derive(serde::Serialize)]
+        struct Test {
+            hash: u128,
+            counter: u64,
+            offset: i128,
+            small: u128,
+            done: bool,
+        }
+
+        let t = Test {
+            hash: u128::MAX,
+            counter: u64::MAX,
+            offset: i128::MIN,
+            small: 5,
+            done: true,
+        };
+
+        let s = super::to_string(&t).unwrap();
+        assert_eq!(
+            s,
+            "RESULT hash=340282366920938463463374607431768211455 counter=18446744073709551615 offset=-170141183460469231731687303715884105728 small=5 done=true"
+        );
+
+        let map: HashMap<&str, ResultItem> = crate::from_string(&s).unwrap();
+        assert_eq!(map["hash"], ResultItem::UInteger128(u128::MAX));
+        assert_eq!(map["counter"], ResultItem::from(u64::MAX as u128));
+        assert_eq!(map["offset"], ResultItem::Integer128(i128::MIN));
+        assert_eq!(map["small"], ResultItem::Integer(5));
+    }
+
+    #[test]
+    fn text_round_trip() {
+        use std::collections::HashMap;
+
+        use crate::ResultItem;
+
+        let mut map = BTreeMap::new();
+        map.insert("a", ResultItem::from("true"));
+        map.insert("b", ResultItem::from("123"));
+        map.insert("c", ResultItem::from("1.5"));
+        map.insert("d", ResultItem::from("007"));
+        map.insert("e", ResultItem::from("text"));
+        map.insert("f", ResultItem::Boolean(true));
+
+        let s = super::to_string(&map).unwrap();
+        assert_eq!(
+            s,
+            r#"RESULT a="true" b="123" c="1.5" d="007" e=text f=true"#
+        );
+
+        let parsed: HashMap<&str, ResultItem> = crate::from_string(&s).unwrap();
+        assert_eq!(parsed.len(), map.len());
+        for (key, value) in &map {
+            assert_eq!(&parsed[key], value, "{key} did not survive the round trip");
+        }
+
+        // Quoted values end at the next quote, so text containing quotes is rejected
+        for text in [r#"say "hi""#, r#""x""#] {
+            let mut map = BTreeMap::new();
+            map.insert("k", ResultItem::from(text));
+            assert_eq!(
+                super::to_string(&map),
+                Err(crate::Erra::InvalidValue("k".to_owned()))
+            );
+        }
+
+        // Characters are written like text of a single character
+        #[derive(serde::Serialize)]
+        struct Chars {
+            a: char,
+            b: char,
+            c: char,
+            d: u8,
+        }
+        let s = super::to_string(&Chars {
+            a: ' ',
+            b: '1',
+            c: 'x',
+            d: 1,
+        })
+        .unwrap();
+        assert_eq!(s, r#"RESULT a=" " b="1" c=x d=1"#);
+        let parsed: HashMap<&str, ResultItem> = crate::from_string(&s).unwrap();
+        assert_eq!(parsed["a"], ResultItem::from(" "));
+        assert_eq!(parsed["b"], ResultItem::from("1"));
+        assert_eq!(parsed["c"], ResultItem::from("x"));
+        assert_eq!(
+            super::to_string(&Chars {
+                a: '"',
+                b: 'b',
+                c: 'c',
+                d: 1,
+            }),
+            Err(crate::Erra::InvalidValue("a".to_owned()))
+        );
+    }
+
+    #[test]
+    fn sequences() {
+        use crate::Erra;
+
+        #[derive(serde::Serialize)]
+        struct Test {
+            name: &'static str,
+            thread: Vec<f64>,
+            pair: (i32, bool),
+            empty: Vec<u8>,
+            nested: [[u8; 2]; 2],
+        }
+
+        let t = Test {
+            name: "run",
+            thread: vec![1.5, 1.75, 2.0],
+            pair: (-3, false),
+            empty: vec![],
+            nested: [[1, 2], [3, 4]],
+        };
+
+        assert_eq!(
+            super::to_string(&t),
+            Ok("RESULT name=run thread_0=1.5 thread_1=1.75 thread_2=2.0 pair_0=-3 pair_1=false nested_0_0=1 nested_0_1=2 nested_1_0=3 nested_1_1=4".to_string())
+        );
+
+        assert_eq!(super::to_string(&vec![1, 2, 3]), Err(Erra::UnnamedSequence));
+    }
+
+    #[test]
+    fn measurements() {
+        use std::collections::HashMap;
+
+        use crate::{Erra, ResultItem};
+
+        let measurements = vec![
+            ("time".to_owned(), 1.5),
+            ("memory used".to_owned(), 12.0),
+            ("throughput".to_owned(), -0.25),
+        ];
+
+        let s = super::to_string_measurements(&measurements).unwrap();
+        assert_eq!(s, r#"RESULT time=1.5 "memory used"=12.0 throughput=-0.25"#);
+
+        // Whole numbers are still read back as floats
+        let parsed: HashMap<&str, ResultItem> = crate::from_string(&s).unwrap();
+        assert_eq!(parsed["memory used"], ResultItem::Float(12.0));
+
+        assert_eq!(
+            super::to_string_measurements(&[] as &[(&str, f64)]),
+            Ok("RESULT".to_string())
+        );
+        assert_eq!(
+            super::to_string_measurements(&[("", 1.0)]),
+            Err(Erra::InvalidKey(String::new()))
+        );
+        assert_eq!(
+            super::to_string_measurements(&[(r#"say "hi""#, 1.0)]),
+            Err(Erra::InvalidKey(r#"say "hi""#.to_owned()))
+        );
+    }
+
+    #[test]
+    fn joined_sequences() {
+        use std::collections::HashMap;
+
+        use super::SerializeOptions;
+        use crate::{Erra, ParseOptions, ResultItem};
+
+        #[derive(serde::Serialize)]
+        struct Test {
+            thread: Vec<f64>,
+            gaps: [Option<u8>; 3],
+            empty: Vec<u8>,
+        }
+
+        let options = SerializeOptions::new().sequence_separator(',');
+        let t = Test {
+            thread: vec![1.5, 1.75, 2.0],
+            gaps: [Some(1), None, Some(3)],
+            empty: vec![],
+        };
+        let line = super::to_string_with_options(&t, &options).unwrap();
+        assert_eq!(line, "RESULT thread=1.5,1.75,2.0 gaps=1,,3");
+
+        let map: HashMap<&str, ResultItem> =
+            crate::from_string_with_options(&line, &ParseOptions::new().sequence_separator(','))
+                .unwrap();
+        assert_eq!(
+            map["thread"],
+            ResultItem::Sequence(vec![
+                ResultItem::Float(1.5),
+                ResultItem::Float(1.75),
+                ResultItem::Float(2.0),
+            ])
+        );
+        assert_eq!(
+            map["gaps"],
+            ResultItem::Sequence(vec![
+                ResultItem::Integer(1),
+                ResultItem::Empty,
+                ResultItem::Integer(3),
+            ])
+        );
+
+        // A single element is written like a single value and read back as one
+        let line = super::to_string_with_options(&HashMap::from([("v", [5])]), &options).unwrap();
+        assert_eq!(line, "RESULT v=5");
+        let map: HashMap<&str, ResultItem> =
+            crate::from_string_with_options(&line, &ParseOptions::new().sequence_separator(','))
+                .unwrap();
+        assert_eq!(map["v"], ResultItem::Integer(5));
+
+        // An empty last element would be written as a trailing separator
+        assert_eq!(
+            super::to_string_with_options(&HashMap::from([("a", [Some(1), None])]), &options),
+            Err(Erra::InvalidSequence("a".to_owned()))
+        );
+        assert_eq!(
+            super::to_string_with_options(&HashMap::from([("a", ["x,y"])]), &options),
+            Err(Erra::InvalidSequence("a".to_owned()))
+        );
+        assert_eq!(
+            super::to_string_with_options(&HashMap::from([("a", [[1, 2]])]), &options),
+            Err(Erra::Unsupported("nested value in a sequence"))
+        );
+    }
+
+    #[test]
+    fn column_template() {
+        use std::collections::HashMap;
+
+        use super::{ExtraColumns, SerializeOptions};
+        use crate::{Erra, ResultItem};
+
+        #[derive(serde::Serialize)]
+        struct Test {
+            time: f64,
+            name: &'static str,
+            extra: bool,
+            memory: Option<u32>,
+        }
+
+        let t = Test {
+            time: 1.5,
+            name: "run",
+            extra: true,
+            memory: None,
+        };
+
+        let options = SerializeOptions::new().column_template(&["name", "memory", "time", "size"]);
+        let s = super::to_string_with_options(&t, &options).unwrap();
+        assert_eq!(s, "RESULT name=run memory= time=1.5 size=");
+
+        let parsed: HashMap<&str, ResultItem> = crate::from_string(&s).unwrap();
+        assert_eq!(parsed.len(), 4);
+        assert_eq!(parsed["memory"], ResultItem::Empty);
+        assert_eq!(parsed["time"], ResultItem::Float(1.5));
+        assert_eq!(parsed["size"], ResultItem::Empty);
+
+        let options = options.extra_columns(ExtraColumns::Error);
+        assert_eq!(
+            super::to_string_with_options(&t, &options),
+            Err(Erra::UnexpectedColumn("extra".to_owned()))
+        );
+    }
+
+    #[test]
+    fn keys() {
+        use std::collections::HashMap;
+
+        use crate::{Erra, ResultItem};
+
+        let mut map = BTreeMap::new();
+        map.insert("a=b", 1);
+        map.insert("with space", 2);
+        map.insert("x=y z", 3);
+        map.insert("plain", 4);
+
+        let s = super::to_string(&map).unwrap();
+        assert_eq!(s, r#"RESULT "a=b"=1 plain=4 "with space"=2 "x=y z"=3"#);
+
+        let parsed: HashMap<&str, ResultItem> = crate::from_string(&s).unwrap();
+        assert_eq!(parsed.len(), map.len());
+        for (key, value) in &map {
+            assert_eq!(
+                parsed[key],
+                ResultItem::Integer(*value),
+                "{key} was not recovered"
+            );
+        }
+
+        let mut map = BTreeMap::new();
+        map.insert(r#"say "hi""#, 1);
+        assert_eq!(
+            super::to_string(&map),
+            Err(Erra::InvalidKey(r#"say "hi""#.to_owned()))
+        );
+
+        let mut map = BTreeMap::new();
+        map.insert("", 1);
+        assert_eq!(super::to_string(&map), Err(Erra::InvalidKey(String::new())));
+    }
+
+    #[test]
+    fn float_format() {
+        use std::collections::HashMap;
+
+        use super::{FloatFormat, SerializeOptions};
+        use crate::ResultItem;
+
+        let mut map = BTreeMap::new();
+        map.insert("a", ResultItem::Float(0.1 + 0.2));
+        map.insert("b", ResultItem::Float(1234.5678));
+        map.insert("c", ResultItem::Float(5.0));
+        map.insert("d", ResultItem::Integer(12));
+        map.insert("e", ResultItem::Boolean(true));
+        map.insert("f", ResultItem::from("1.23456"));
+
+        assert_eq!(
+            super::to_string(&map),
+            Ok(
+                r#"RESULT a=0.30000000000000004 b=1234.5678 c=5.0 d=12 e=true f="1.23456""#
+                    .to_owned()
+            )
+        );
+
+        let options = SerializeOptions::new().float_format(FloatFormat::Decimals(3));
+        let s = super::to_string_with_options(&map, &options).unwrap();
+        assert_eq!(
+            s,
+            r#"RESULT a=0.300 b=1234.568 c=5.000 d=12 e=true f="1.23456""#
+        );
+
+        let parsed: HashMap<&str, ResultItem> = crate::from_string(&s).unwrap();
+        assert_eq!(parsed["a"], ResultItem::Float(0.3));
+        assert_eq!(parsed["c"], ResultItem::Float(5.0));
+        assert_eq!(parsed["d"], ResultItem::Integer(12));
+
+        let options = SerializeOptions::new().float_format(FloatFormat::SignificantFigures(3));
+        let s = super::to_string_with_options(&map, &options).unwrap();
+        assert_eq!(s, r#"RESULT a=0.3 b=1230.0 c=5.0 d=12 e=true f="1.23456""#);
+
+        let parsed: HashMap<&str, ResultItem> = crate::from_string(&s).unwrap();
+        assert_eq!(parsed["b"], ResultItem::Float(1230.0));
+        assert_eq!(parsed["c"], ResultItem::Float(5.0));
+    }
+
+    #[test]
+    fn empty_text() {
+        use std::collections::HashMap;
+
+        use crate::ResultItem;
+
+        #[derive(serde::Serialize)]
+        struct Test {
+            measured: String,
+            unmeasured: Option<String>,
+            last: &'static str,
+        }
+
+        let t = Test {
+            measured: String::new(),
+            unmeasured: None,
+            last: "",
+        };
+
+        let s = super::to_string(&t).unwrap();
+        assert_eq!(s, r#"RESULT measured="" last="""#);
+
+        let parsed: HashMap<&str, ResultItem> = crate::from_string(&s).unwrap();
+        let mut expected = HashMap::<&str, ResultItem>::new();
+        expected.insert("measured", ResultItem::from(""));
+        expected.insert("last", ResultItem::from(""));
+        assert_eq!(parsed, expected);
+    }
+
+    #[test]
+    fn schema_version() {
+        use std::collections::HashMap;
+
+        use super::SerializeOptions;
+        use crate::ResultItem;
+
+        #[derive(serde::Serialize)]
+        struct Test {
+            a: i32,
+            b: bool,
+        }
+
+        let options = SerializeOptions::new().schema_version(3);
+        let s = super::to_string_with_options(&Test { a: 1, b: true }, &options).unwrap();
+        assert_eq!(s, "RESULT __schema=3 a=1 b=true");
+
+        let (version, parsed): (_, HashMap<&str, ResultItem>) =
+            crate::from_string_versioned(&s).unwrap();
+        assert_eq!(version, Some(3));
+        let mut expected = HashMap::<&str, ResultItem>::new();
+        expected.insert("a", ResultItem::Integer(1));
+        expected.insert("b", ResultItem::Boolean(true));
+        assert_eq!(parsed, expected);
+
+        let (version, parsed): (_, HashMap<&str, ResultItem>) =
+            crate::from_string_versioned("RESULT a=1 b=true").unwrap();
+        assert_eq!(version, None);
+        assert_eq!(parsed, expected);
+
+        // An item named like the schema version would be read back as the version
+        let mut map = BTreeMap::new();
+        map.insert(crate::SCHEMA_VERSION_KEY, 5);
+        assert_eq!(
+            super::to_string_with_options(&map, &options),
+            Err(crate::Erra::InvalidKey(
+                crate::SCHEMA_VERSION_KEY.to_owned()
+            ))
+        );
+    }
+
+    #[test]
+    fn decimal_separator() {
+        use std::collections::HashMap;
+
+        use super::{FloatFormat, SerializeOptions};
+        use crate::{ParseOptions, ResultItem};
+
+        let mut map = BTreeMap::new();
+        map.insert("a", ResultItem::Float(2.75));
+        map.insert("b", ResultItem::Float(-0.5));
+        map.insert("c", ResultItem::Integer(12));
+        map.insert("d", ResultItem::from("2,75"));
+        map.insert("e", ResultItem::from("2.75"));
+
+        let options = SerializeOptions::new().decimal_separator(',');
+        let s = super::to_string_with_options(&map, &options).unwrap();
+        assert_eq!(s, r#"RESULT a=2,75 b=-0,5 c=12 d="2,75" e=2.75"#);
+
+        let parse_options = ParseOptions::new().decimal_separator(',');
+        let parsed: HashMap<&str, ResultItem> =
+            crate::from_string_with_options(&s, &parse_options).unwrap();
+        assert_eq!(parsed.len(), map.len());
+        for (key, value) in &map {
+            assert_eq!(&parsed[key], value, "{key} did not survive the round trip");
+        }
+
+        let options = options.float_format(FloatFormat::Decimals(2));
+        assert_eq!(
+            super::to_string_with_options(&map, &options),
+            Ok(r#"RESULT a=2,75 b=-0,50 c=12 d="2,75" e=2.75"#.to_owned())
+        );
+    }
+
+    #[test]
+    fn key_value_separator() {
+        use std::collections::HashMap;
+
+        use super::SerializeOptions;
+        use crate::{ParseOptions, ResultItem};
+
+        let mut map = BTreeMap::new();
+        map.insert("a", ResultItem::Integer(1));
+        map.insert("b=c", ResultItem::Float(2.5));
+        map.insert("d>", ResultItem::from("x=>y"));
+        map.insert("with space", ResultItem::Boolean(true));
+
+        let options = SerializeOptions::new().key_value_separator("=>");
+        let s = super::to_string_with_options(&map, &options).unwrap();
+        assert_eq!(s, r#"RESULT a=>1 "b=c"=>2.5 d>=>x=>y "with space"=>true"#);
+
+        let parse_options = ParseOptions::new().key_value_separator("=>");
+        let parsed: HashMap<&str, ResultItem> =
+            crate::from_string_with_options(&s, &parse_options).unwrap();
+        assert_eq!(parsed.len(), map.len());
+        for (key, value) in &map {
+            assert_eq!(&parsed[key], value, "{key} did not survive the round trip");
+        }
+    }
+
+    #[test]
+    fn map_fast_path() {
+        let mut map = BTreeMap::new();
+        map.insert("time".to_owned(), 0.1 + 0.2);
+        map.insert("a key".to_owned(), -12.0);
+        map.insert("with=sign".to_owned(), 1e20);
+        map.insert("nan".to_owned(), f64::NAN);
+
+        assert_eq!(super::to_string_map(&map), super::to_string(&map));
+
+        let mut map = BTreeMap::new();
+        map.insert("flag".to_owned(), true);
+        map.insert("other flag".to_owned(), false);
+
+        assert_eq!(super::to_string_map(&map), super::to_string(&map));
+
+        let mut map = BTreeMap::new();
+        map.insert(String::new(), 1.0);
+        assert_eq!(super::to_string_map(&map), super::to_string(&map));
+
+        let mut map = BTreeMap::new();
+        map.insert("text".to_owned(), "true");
+        map.insert("quoted".to_owned(), r#"say "hi""#);
+        assert_eq!(super::to_string_map(&map), super::to_string(&map));
+    }
+
+    #[test]
+    fn sorted() {
+        use std::collections::HashMap;
+
+        // Separately built maps have their own random state and insertion order, so they iterate differently
+        let map: HashMap<String, usize> = (0..100).map(|i| (format!("key {i}"), i)).collect();
+        let reversed: HashMap<String, usize> =
+            (0..100).rev().map(|i| (format!("key {i}"), i)).collect();
+        let first = super::to_string_sorted(&map).unwrap();
+        let second = super::to_string_sorted(&reversed).unwrap();
+        assert_eq!(first, second);
+        assert!(first.starts_with(r#"RESULT "key 0"=0 "key 1"=1 "key 10"=10 "key 11"=11"#));
+
+        #[derive(serde::Serialize)]
+        struct Test {
+            b: i32,
+            a: i32,
+            #[serde(flatten)]
+            c: BTreeMap<&'static str, i32>,
+        }
+
+        let mut t = Test {
+            b: 1,
+            a: 2,
+            c: BTreeMap::new(),
+        };
+        t.c.insert("b", 3);
+
+        assert_eq!(super::to_string(&t), Ok("RESULT b=1 a=2 b=3".to_owned()));
+        assert_eq!(
+            super::to_string_sorted(&t),
+            Ok("RESULT a=2 b=1 b=3".to_owned())
+        );
+    }
+
+    #[test]
+    fn empty_text_and_none() {
+        use std::collections::HashMap;
+
+        use crate::ResultItem;
+
+        #[derive(serde::Serialize)]
+        struct Test {
+            a: Option<&'static str>,
+            b: Option<&'static str>,
+            c: Option<String>,
+        }
+
+        let t = Test {
+            a: Some(""),
+            b: None,
+            c: Some(String::new()),
+        };
+
+        let s = super::to_string(&t).unwrap();
+        assert_eq!(s, r#"RESULT a="" c="""#);
+
+        let parsed: HashMap<&str, ResultItem> = crate::from_string(&s).unwrap();
+        assert_eq!(parsed.get("a"), Some(&ResultItem::from("")));
+        assert_eq!(parsed.get("b"), None);
+        assert_eq!(parsed.get("c"), Some(&ResultItem::from("")));
+
+        let mut map = BTreeMap::new();
+        map.insert("empty", ResultItem::Empty);
+        map.insert("text", ResultItem::from(""));
+        assert_eq!(super::to_string(&map), Ok(r#"RESULT text="""#.to_owned()));
+    }
+
+    #[test]
+    fn nested_structs() {
+        use std::collections::HashMap;
+
+        use crate::ResultItem;
+
+        #[derive(serde::Serialize)]
+        struct Stats {
+            time: f64,
+            mem: usize,
+        }
+
+        #[derive(serde::Serialize)]
+        struct Phase {
+            name: &'static str,
+            stats: Stats,
+        }
+
+        #[derive(serde::Serialize)]
+        struct Record {
+            build: Stats,
+            query: Stats,
+            phases: Vec<Phase>,
+            counts: BTreeMap<&'static str, u32>,
+            #[serde(flatten)]
+            flat: Stats,
+        }
+
+        let mut t = Record {
+            build: Stats {
+                time: 1.5,
+                mem: 100,
+            },
+            query: Stats {
+                time: 0.25,
+                mem: 20,
+            },
+            phases: vec![Phase {
+                name: "load",
+                stats: Stats { time: 0.5, mem: 8 },
+            }],
+            counts: BTreeMap::new(),
+            flat: Stats { time: 2.5, mem: 1 },
+        };
+        t.counts.insert("hits", 3);
+
+        let s = super::to_string(&t).unwrap();
+        assert_eq!(
+            s,
+            "RESULT build.time=1.5 build.mem=100 query.time=0.25 query.mem=20 phases_0.name=load phases_0.stats.time=0.5 phases_0.stats.mem=8 counts.hits=3 time=2.5 mem=1"
+        );
+
+        let parsed: Vec<(&str, ResultItem)> = crate::from_string(&s).unwrap();
+        let keys: HashMap<&str, ResultItem> = parsed.iter().cloned().collect();
+        assert_eq!(parsed.len(), 10);
+        assert_eq!(keys.len(), 10, "keys are not unique");
+        assert_eq!(keys["query.time"], ResultItem::Float(0.25));
+        assert_eq!(keys["phases_0.stats.mem"], ResultItem::Integer(8));
+    }
+
+    #[test]
+    fn unit_variants() {
+        use std::collections::HashMap;
+
+        use crate::ResultItem;
+
+        #[derive(serde::Serialize)]
+        enum Mode {
+            Fast,
+            #[serde(rename = "slow mode")]
+            Slow,
+        }
+
+        #[derive(serde::Serialize)]
+        struct Test {
+            mode: Mode,
+            other: Mode,
+            fallback: Option<Mode>,
+        }
+
+        let t = Test {
+            mode: Mode::Fast,
+            other: Mode::Slow,
+            fallback: None,
+        };
+
+        let s = super::to_string(&t).unwrap();
+        assert_eq!(s, r#"RESULT mode=Fast other="slow mode""#);
+
+        let parsed: HashMap<&str, ResultItem> = crate::from_string(&s).unwrap();
+        assert_eq!(parsed["mode"], ResultItem::from("Fast"));
+        assert_eq!(parsed["other"], ResultItem::from("slow mode"));
+
+        // Empty result items are still left out instead of being written as their variant name
+        let mut map = BTreeMap::new();
+        map.insert("a", ResultItem::Empty);
+        map.insert("b", ResultItem::Integer(1));
+        assert_eq!(super::to_string(&map), Ok("RESULT b=1".to_string()));
+
+        // Other enums are written by their variant name, even if they share the names
+        {
+            #[derive(serde::Serialize)]
+            enum ResultItem {
+                Empty,
+            }
+            let mut map = BTreeMap::new();
+            map.insert("a", ResultItem::Empty);
+            assert_eq!(super::to_string(&map), Ok("RESULT a=Empty".to_string()));
+        }
     }
 }